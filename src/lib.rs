@@ -3,20 +3,53 @@
 
 
 use std::{io, mem, ptr};
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ffi::{c_void, OsString};
-use std::hash::Hash;
-use std::ops::{BitAnd, Neg};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::BitAnd;
 use std::ops::Deref;
 use std::os::windows::prelude::OsStringExt;
-use std::sync::OnceLock;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use std::mem::size_of;
 use std::ptr::{null, null_mut};
-use dpi::{PhysicalPosition, PhysicalSize};
+use dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyEOFError, PyKeyError, PyOSError, PyRuntimeWarning, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
 use pyo3::pymodule;
 use windows_sys::core::HRESULT;
-use windows_sys::Win32::Foundation::{BOOL, HWND, WPARAM, LPARAM, POINT, RECT, POINTL, S_OK};
+use windows_sys::Win32::Devices::Display::{
+    DestroyPhysicalMonitors, DisplayConfigGetDeviceInfo, DisplayConfigSetDeviceInfo, GetDisplayConfigBufferSizes,
+    GetMonitorBrightness, GetNumberOfPhysicalMonitorsFromHMONITOR, GetPhysicalMonitorsFromHMONITOR, QueryDisplayConfig,
+    SetDisplayConfig, SetMonitorBrightness, DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO,
+    DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+    DISPLAYCONFIG_DEVICE_INFO_HEADER, DISPLAYCONFIG_DEVICE_INFO_SET_ADVANCED_COLOR_STATE,
+    DISPLAYCONFIG_DEVICE_INFO_TYPE, DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_MODE_INFO,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_COMPONENT_VIDEO, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_COMPOSITE_VIDEO,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_USB_TUNNEL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_D_JPN, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_VIRTUAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_WIRED,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_OTHER,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SDI, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SDTVDONGLE,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SVIDEO, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EXTERNAL, DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE,
+    DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE_0, DISPLAYCONFIG_SOURCE_DEVICE_NAME, DISPLAYCONFIG_TARGET_DEVICE_NAME,
+    DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY, PHYSICAL_MONITOR, QDC_ONLY_ACTIVE_PATHS, SDC_APPLY, SDC_TOPOLOGY_CLONE,
+    SDC_TOPOLOGY_EXTEND, SDC_TOPOLOGY_EXTERNAL, SDC_TOPOLOGY_INTERNAL,
+};
+use windows_sys::Win32::Foundation::{
+    BOOL, ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS, HANDLE, HWND, LPARAM, LRESULT, LUID, POINT, POINTL, RECT, S_OK, WPARAM,
+};
 use windows_sys::Win32::Graphics::Gdi::{
     DEVMODEW, ENUM_CURRENT_SETTINGS, EnumDisplayMonitors, EnumDisplaySettingsExW,
     GetMonitorInfoW, HDC,
@@ -25,9 +58,25 @@ use windows_sys::Win32::Graphics::Gdi::{
 };
 use windows_sys::Win32::Graphics::Gdi::*;
 
-use windows_sys::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryA};
+use windows_sys::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress, LoadLibraryA};
+use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY, HKEY_LOCAL_MACHINE, RRF_RT_REG_BINARY};
 use windows_sys::Win32::UI::HiDpi::{
-    MDT_EFFECTIVE_DPI, MONITOR_DPI_TYPE,
+    DPI_AWARENESS, DPI_AWARENESS_CONTEXT, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE,
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, DPI_AWARENESS_CONTEXT_SYSTEM_AWARE, DPI_AWARENESS_CONTEXT_UNAWARE,
+    DPI_AWARENESS_PER_MONITOR_AWARE,
+    DPI_AWARENESS_SYSTEM_AWARE, MDT_ANGULAR_DPI, MDT_EFFECTIVE_DPI, MDT_RAW_DPI, MONITOR_DPI_TYPE,
+    PROCESS_DPI_AWARENESS, PROCESS_DPI_UNAWARE, PROCESS_PER_MONITOR_DPI_AWARE, PROCESS_SYSTEM_DPI_AWARE,
+};
+use windows_sys::Win32::UI::ColorSystem::{GetDeviceGammaRamp, SetDeviceGammaRamp};
+use windows_sys::Win32::UI::Shell::{
+    SHAppBarMessage, ABE_BOTTOM, ABE_LEFT, ABE_RIGHT, ABE_TOP, ABM_GETTASKBARPOS, ABM_SETPOS, APPBARDATA,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, FindWindowW, GetCursorPos, GetMessageW,
+    GetSystemMetrics, IsProcessDPIAware, PostMessageW, PostQuitMessage, RegisterClassExW, SetWindowPos,
+    TranslateMessage, EDD_GET_DEVICE_INTERFACE_NAME, HWND_BROADCAST, MONITORINFOF_PRIMARY, MSG, SC_MONITORPOWER,
+    SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_REMOTESESSION, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SWP_NOACTIVATE,
+    SWP_NOZORDER, WM_CLOSE, WM_DESTROY, WM_DISPLAYCHANGE, WM_DPICHANGED, WM_SETTINGCHANGE, WM_SYSCOMMAND, WNDCLASSEXW,
 };
 
 pub const BASE_DPI: u32 = 96;
@@ -53,15 +102,41 @@ impl MonitorHandle {
         MonitorHandle(hmonitor)
     }
 
+    /// The device name, e.g. `\\.\DISPLAY1`, or the error from `GetMonitorInfoW` if the handle is
+    /// no longer valid.
+    pub fn try_name(&self) -> io::Result<String> {
+        let monitor_info = get_monitor_info(self.0)?;
+        Ok(decode_wide(&monitor_info.szDevice).to_string_lossy().to_string())
+    }
+
+    /// Like `try_name`, but `None` on an invalid handle.
     #[inline]
     pub fn name(&self) -> Option<String> {
-        let monitor_info = get_monitor_info(self.0).unwrap();
-        Some(decode_wide(&monitor_info.szDevice).to_string_lossy().to_string())
+        self.try_name().ok()
+    }
+
+    /// The device name exactly as Windows reports it in `szDevice`, NUL-terminated, for passing
+    /// back to the `*DisplaySettings*W` functions without a lossy trip through `String`.
+    pub(crate) fn wide_name(&self) -> Option<Vec<u16>> {
+        Some(wide_device_name(&get_monitor_info(self.0).ok()?))
     }
 
+    /// The number at the end of the device name, e.g. 2 for `\\.\DISPLAY2`. It usually, but not
+    /// always, matches the number shown in the Settings app's display arrangement.
+    pub fn display_number(&self) -> Option<u32> {
+        display_number_from_name(&self.name()?)
+    }
+
+    /// The device name, or an empty string on an invalid handle.
     #[inline]
     pub fn native_identifier(&self) -> String {
-        self.name().unwrap()
+        self.name().unwrap_or_default()
+    }
+
+    /// The device name, or the handle in hex if the name can't be read, so that per-monitor
+    /// results keyed by it never collide.
+    pub(crate) fn result_key(&self) -> String {
+        self.name().unwrap_or_else(|| format!("{:#x}", self.0))
     }
 
     #[inline]
@@ -69,22 +144,41 @@ impl MonitorHandle {
         self.0
     }
 
-    #[inline]
-    pub fn size(&self) -> PhysicalSize<u32> {
-        let rc_monitor = get_monitor_info(self.0).unwrap().monitorInfo.rcMonitor;
-        PhysicalSize {
+    pub fn try_size(&self) -> io::Result<PhysicalSize<u32>> {
+        let rc_monitor = get_monitor_info(self.0)?.monitorInfo.rcMonitor;
+        Ok(PhysicalSize {
             width: (rc_monitor.right - rc_monitor.left) as u32,
             height: (rc_monitor.bottom - rc_monitor.top) as u32,
-        }
+        })
+    }
+
+    /// Like `try_size`, but 0x0 on an invalid handle. This never panics; check `is_valid` or use
+    /// `try_size` to tell a stale handle apart.
+    #[inline]
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.try_size().unwrap_or(PhysicalSize { width: 0, height: 0 })
+    }
+
+    /// The area of `size` in pixels.
+    #[inline]
+    pub fn area(&self) -> u64 {
+        let size = self.size();
+        size.width as u64 * size.height as u64
+    }
+
+    /// Whether the handle still refers to an attached monitor. Handles go stale when the display
+    /// topology changes.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        get_monitor_info(self.0).is_ok()
     }
 
     #[inline]
     pub fn refresh_rate_millihertz(&self) -> Option<u32> {
         let monitor_info = get_monitor_info(self.0).ok()?;
         let device_name = monitor_info.szDevice.as_ptr();
+        let mut mode = DevModeBuilder::new().build();
         unsafe {
-            let mut mode: DEVMODEW = mem::zeroed();
-            mode.dmSize = mem::size_of_val(&mode) as u16;
             if EnumDisplaySettingsExW(device_name, ENUM_CURRENT_SETTINGS, &mut mode, 0)
                 == false.into()
             {
@@ -95,24 +189,516 @@ impl MonitorHandle {
         }
     }
 
+    /// The refresh rate in hertz, e.g. 59.94. Prefers the exact rate of the display path, since
+    /// `dmDisplayFrequency` (and so `refresh_rate_millihertz`) is rounded to whole hertz.
+    pub fn refresh_rate_hz(&self) -> Option<f64> {
+        match self.refresh_rate_rational() {
+            Some((numerator, denominator)) => Some(numerator as f64 / denominator as f64),
+            None => self.refresh_rate_millihertz().map(|millihertz| millihertz as f64 / 1000.0),
+        }
+    }
+
+    /// The color depth of the current mode (`dmBitsPerPel`).
+    #[inline]
+    pub fn bits_per_pixel(&self) -> Option<u32> {
+        let monitor_info = get_monitor_info(self.0).ok()?;
+        let device_name = monitor_info.szDevice.as_ptr();
+        let mut mode = DevModeBuilder::new().build();
+        unsafe {
+            if EnumDisplaySettingsExW(device_name, ENUM_CURRENT_SETTINGS, &mut mode, 0)
+                == false.into()
+            {
+                None
+            } else {
+                Some(mode.dmBitsPerPel)
+            }
+        }
+    }
+
+    pub fn try_position(&self) -> io::Result<PhysicalPosition<i32>> {
+        let rc_monitor = get_monitor_info(self.0)?.monitorInfo.rcMonitor;
+        Ok(PhysicalPosition { x: rc_monitor.left, y: rc_monitor.top })
+    }
+
+    /// Like `try_position`, but (0, 0) on an invalid handle, which is indistinguishable from the
+    /// primary monitor's position; use `try_position` when that matters.
     #[inline]
     pub fn position(&self) -> PhysicalPosition<i32> {
+        self.try_position().unwrap_or(PhysicalPosition { x: 0, y: 0 })
+    }
+
+    /// The monitor's `rcWork`: its rectangle minus the taskbar and any other docked app bars.
+    #[inline]
+    pub(crate) fn work_rect(&self) -> RECT {
         get_monitor_info(self.0)
-            .map(|info| {
-                let rc_monitor = info.monitorInfo.rcMonitor;
-                PhysicalPosition { x: rc_monitor.left, y: rc_monitor.top }
-            })
-            .unwrap_or(PhysicalPosition { x: 0, y: 0 })
+            .map(|info| info.monitorInfo.rcWork)
+            .unwrap_or(RECT { left: 0, top: 0, right: 0, bottom: 0 })
+    }
+
+    /// The position and size of the monitor's work area, which excludes the taskbar and any other
+    /// docked app bars.
+    #[inline]
+    pub fn work_area(&self) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+        let rc_work = self.work_rect();
+        (
+            PhysicalPosition { x: rc_work.left, y: rc_work.top },
+            PhysicalSize {
+                width: (rc_work.right - rc_work.left) as u32,
+                height: (rc_work.bottom - rc_work.top) as u32,
+            },
+        )
+    }
+
+    /// The midpoint of the monitor's rectangle.
+    #[inline]
+    pub fn center(&self) -> PhysicalPosition<i32> {
+        let position = self.position();
+        let size = self.size();
+        PhysicalPosition {
+            x: position.x + (size.width / 2) as i32,
+            y: position.y + (size.height / 2) as i32,
+        }
+    }
+
+    /// The top-left corner for a window of `size` centered in the work area, so it stays clear of
+    /// the taskbar. A window larger than the work area overhangs it evenly on both sides.
+    pub fn center_rect(&self, size: PhysicalSize<u32>) -> PhysicalPosition<i32> {
+        let (position, work_size) = self.work_area();
+        PhysicalPosition {
+            x: position.x + (work_size.width as i32 - size.width as i32) / 2,
+            y: position.y + (work_size.height as i32 - size.height as i32) / 2,
+        }
     }
 
     #[inline]
     pub fn scale_factor(&self) -> f64 {
         dpi_to_scale_factor(get_monitor_dpi(self.0).unwrap_or(96))
     }
+
+    /// The size in device-independent pixels: the physical size divided by `scale_factor`.
+    pub fn logical_size(&self) -> LogicalSize<f64> {
+        self.size().to_logical(self.scale_factor())
+    }
+
+    /// The current rotation of the monitor in degrees: 0, 90, 180 or 270. Returns 0 when the driver
+    /// doesn't report an orientation.
+    pub fn orientation_degrees(&self) -> u32 {
+        let Some(devmode) = self.wide_name().and_then(|wide_name| get_dev_mode_wide(&wide_name).ok()) else {
+            return 0;
+        };
+        if !has_flag(devmode.dmFields, DM_DISPLAYORIENTATION) {
+            return 0;
+        }
+        match unsafe { devmode.Anonymous1.Anonymous2.dmDisplayOrientation } {
+            DMDO_90 => 90,
+            DMDO_180 => 180,
+            DMDO_270 => 270,
+            _ => 0,
+        }
+    }
+
+    #[inline]
+    pub fn orientation(&self) -> Orientation {
+        Orientation::from_degrees(self.orientation_degrees()).unwrap_or(Orientation::Landscape)
+    }
+
+    /// Rotates the monitor, swapping its width and height when going between landscape and
+    /// portrait so that e.g. a 1920x1080 panel becomes 1080x1920 at 90 degrees. Unless `persist`
+    /// is set, the change is lost at the next reboot or sign-out.
+    pub fn set_orientation(&self, orientation: Orientation, persist: bool) -> io::Result<()> {
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let current = get_dev_mode_wide(&wide_name).map_err(io::Error::other)?;
+        let (mut width, mut height) = (current.dmPelsWidth, current.dmPelsHeight);
+        if self.orientation().is_portrait() != orientation.is_portrait() {
+            mem::swap(&mut width, &mut height);
+        }
+        let devmode = DevModeBuilder::from_devmode(current)
+            .width(width)
+            .height(height)
+            .orientation(orientation.dmdo())
+            .build();
+        change_display_settings_wide(&wide_name, &devmode, persist_flags(persist))
+    }
+
+    /// The panel's physical width and height in millimeters as reported by the driver, or `None`
+    /// if it reports zeros, as virtual displays commonly do.
+    pub fn physical_size_mm(&self) -> Option<(u32, u32)> {
+        let wide_name = self.wide_name()?;
+        let driver = wide_string("DISPLAY");
+        unsafe {
+            let hdc = CreateDCW(driver.as_ptr(), wide_name.as_ptr(), null(), null());
+            if hdc == 0 {
+                return None;
+            }
+            let width = GetDeviceCaps(hdc, HORZSIZE as i32);
+            let height = GetDeviceCaps(hdc, VERTSIZE as i32);
+            DeleteDC(hdc);
+            if width <= 0 || height <= 0 {
+                None
+            } else {
+                Some((width as u32, height as u32))
+            }
+        }
+    }
+
+    /// The raw `MONITORINFO.dwFlags` bits (e.g. `MONITORINFOF_PRIMARY`).
+    #[inline]
+    pub fn flags(&self) -> u32 {
+        get_monitor_info(self.0).map(|info| info.monitorInfo.dwFlags).unwrap_or(0)
+    }
+
+    /// Whether this is the primary monitor, according to `MONITORINFOF_PRIMARY`.
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        has_flag(self.flags(), MONITORINFOF_PRIMARY)
+    }
 }
 
+/// What the individual `MonitorHandle` getters report about a monitor, read with one
+/// `GetMonitorInfoW`, one `GetDpiForMonitor` and one `EnumDisplaySettingsExW` call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub monitor: MonitorHandle,
+    pub name: String,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub work_area: (PhysicalPosition<i32>, PhysicalSize<u32>),
+    pub scale_factor: f64,
+    pub refresh_rate_millihertz: Option<u32>,
+    /// The raw `MONITORINFO.dwFlags`.
+    pub flags: u32,
+}
 
-pub fn available_monitors() -> VecDeque<MonitorHandle> {
+impl MonitorInfo {
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        has_flag(self.flags, MONITORINFOF_PRIMARY)
+    }
+}
+
+fn rect_position_and_size(rect: &RECT) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    (
+        PhysicalPosition { x: rect.left, y: rect.top },
+        PhysicalSize { width: (rect.right - rect.left) as u32, height: (rect.bottom - rect.top) as u32 },
+    )
+}
+
+impl MonitorHandle {
+    /// Everything about the monitor at once, or the error from `GetMonitorInfoW` if the handle is
+    /// no longer valid.
+    pub fn info(&self) -> io::Result<MonitorInfo> {
+        let monitor_info = get_monitor_info(self.0)?;
+        let (position, size) = rect_position_and_size(&monitor_info.monitorInfo.rcMonitor);
+        let mut mode = DevModeBuilder::new().build();
+        let found =
+            unsafe { EnumDisplaySettingsExW(monitor_info.szDevice.as_ptr(), ENUM_CURRENT_SETTINGS, &mut mode, 0) };
+        Ok(MonitorInfo {
+            monitor: self.clone(),
+            name: decode_wide(&monitor_info.szDevice).to_string_lossy().into_owned(),
+            position,
+            size,
+            work_area: rect_position_and_size(&monitor_info.monitorInfo.rcWork),
+            scale_factor: dpi_to_scale_factor(get_monitor_dpi(self.0).unwrap_or(BASE_DPI)),
+            refresh_rate_millihertz: (found != false.into()).then(|| mode.dmDisplayFrequency * 1000),
+            flags: monitor_info.monitorInfo.dwFlags,
+        })
+    }
+}
+
+/// `MonitorHandle::info` for every attached monitor, for callers that poll everything about every
+/// monitor. Monitors detached while the snapshot is taken are left out.
+pub fn snapshot_all_monitors() -> Vec<MonitorInfo> {
+    monitors().filter_map(|monitor| monitor.info().ok()).collect()
+}
+
+
+/// A display mode reported by `EnumDisplaySettingsExW`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    /// In hertz; 0 means the hardware's default rate.
+    pub refresh_rate: u32,
+    pub bits_per_pixel: u32,
+}
+
+impl From<&DEVMODEW> for DisplayMode {
+    fn from(devmode: &DEVMODEW) -> Self {
+        DisplayMode {
+            width: devmode.dmPelsWidth,
+            height: devmode.dmPelsHeight,
+            // Drivers report the hardware default as either 0 or 1.
+            refresh_rate: match devmode.dmDisplayFrequency {
+                0 | 1 => 0,
+                frequency => frequency,
+            },
+            bits_per_pixel: devmode.dmBitsPerPel,
+        }
+    }
+}
+
+/// A display rotation, named by which way the desktop ends up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// 0 degrees.
+    Landscape,
+    /// 90 degrees.
+    Portrait,
+    /// 180 degrees.
+    LandscapeFlipped,
+    /// 270 degrees.
+    PortraitFlipped,
+}
+
+impl Orientation {
+    /// Returns `None` for anything but 0, 90, 180 or 270.
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees {
+            0 => Some(Orientation::Landscape),
+            90 => Some(Orientation::Portrait),
+            180 => Some(Orientation::LandscapeFlipped),
+            270 => Some(Orientation::PortraitFlipped),
+            _ => None,
+        }
+    }
+
+    pub fn degrees(self) -> u32 {
+        match self {
+            Orientation::Landscape => 0,
+            Orientation::Portrait => 90,
+            Orientation::LandscapeFlipped => 180,
+            Orientation::PortraitFlipped => 270,
+        }
+    }
+
+    fn dmdo(self) -> DEVMODE_DISPLAY_ORIENTATION {
+        match self {
+            Orientation::Landscape => DMDO_DEFAULT,
+            Orientation::Portrait => DMDO_90,
+            Orientation::LandscapeFlipped => DMDO_180,
+            Orientation::PortraitFlipped => DMDO_270,
+        }
+    }
+
+    #[inline]
+    pub fn is_portrait(self) -> bool {
+        matches!(self, Orientation::Portrait | Orientation::PortraitFlipped)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+            Orientation::LandscapeFlipped => "landscape_flipped",
+            Orientation::PortraitFlipped => "portrait_flipped",
+        }
+    }
+}
+
+/// The current refresh rate next to the best ones available, all in hertz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RefreshSummary {
+    pub current: u32,
+    /// The highest refresh rate supported at the current resolution.
+    pub max_at_current_res: u32,
+    /// The highest refresh rate supported at any resolution.
+    pub max_overall: u32,
+}
+
+/// Upper bound on the number of `EnumDisplaySettingsExW` calls made for one display. Real displays
+/// report at most a few hundred modes.
+const MAX_MODE_ENUMERATIONS: u32 = 4096;
+
+/// Some drivers never return `false` from `EnumDisplaySettingsExW` and keep cycling through the
+/// same modes instead; give up after this many consecutive modes that were already seen.
+const MAX_REPEATED_MODES: u32 = 256;
+
+/// Collects the distinct modes produced by `next_mode`, which is called with increasing mode
+/// numbers and returns `None` once the driver runs out of modes.
+fn collect_display_modes<F>(mut next_mode: F) -> Vec<DisplayMode>
+where
+    F: FnMut(u32) -> Option<DisplayMode>,
+{
+    let mut seen = HashSet::new();
+    let mut modes = Vec::new();
+    let mut repeated = 0;
+    for mode_number in 0..MAX_MODE_ENUMERATIONS {
+        let Some(mode) = next_mode(mode_number) else {
+            break;
+        };
+        if seen.insert(mode) {
+            modes.push(mode);
+            repeated = 0;
+        } else {
+            repeated += 1;
+            if repeated >= MAX_REPEATED_MODES {
+                break;
+            }
+        }
+    }
+    modes
+}
+
+impl MonitorHandle {
+    /// Every distinct mode the display supports, in the order the driver first reports them.
+    pub fn supported_modes(&self) -> Vec<DisplayMode> {
+        let Ok(monitor_info) = get_monitor_info(self.0) else {
+            return Vec::new();
+        };
+        collect_display_modes(|mode_number| {
+            let mut devmode = DevModeBuilder::new().build();
+            let found = unsafe {
+                EnumDisplaySettingsExW(monitor_info.szDevice.as_ptr(), mode_number, &mut devmode, 0)
+            };
+            if found == false.into() {
+                None
+            } else {
+                Some(DisplayMode::from(&devmode))
+            }
+        })
+    }
+
+    /// The supported modes without duplicates, sorted by resolution, then refresh rate, then bit
+    /// depth.
+    pub fn display_modes(&self) -> Vec<DisplayMode> {
+        let mut modes = self.supported_modes();
+        modes.sort_unstable();
+        modes
+    }
+
+    /// The supported modes that are at least `min_width` x `min_height` at `min_refresh` Hz.
+    pub fn supported_modes_filtered(&self, min_width: u32, min_height: u32, min_refresh: u32) -> Vec<DisplayMode> {
+        self.supported_modes()
+            .into_iter()
+            .filter(|mode| mode.width >= min_width && mode.height >= min_height && mode.refresh_rate >= min_refresh)
+            .collect()
+    }
+
+    /// Changes the monitor's resolution, keeping its refresh rate and bit depth where the driver
+    /// allows. Fails with `InvalidInput`, without touching the display, if the monitor doesn't
+    /// support the resolution. Unless `persist` is set, the change is lost at the next reboot or
+    /// sign-out.
+    pub fn set_mode(&self, width: u32, height: u32, persist: bool) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        if !self.supports_mode(width, height, None) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: {}x{} is not a supported mode", name, width, height),
+            ));
+        }
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let devmode = DevModeBuilder::new().width(width).height(height).build();
+        change_display_settings_wide(&wide_name, &devmode, persist_flags(persist))
+    }
+
+    /// Changes only the refresh rate, keeping the current resolution. Fails with `InvalidInput`,
+    /// without touching the display, if `hz` isn't offered at the current resolution. Unless `persist` is set, the change is lost at the next reboot or
+    /// sign-out.
+    pub fn set_refresh_rate(&self, hz: u32, persist: bool) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let current = get_dev_mode_wide(&wide_name).map_err(io::Error::other)?;
+        if !self.supports_mode(current.dmPelsWidth, current.dmPelsHeight, Some(hz)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: {} Hz is not supported at {}x{}", name, hz, current.dmPelsWidth, current.dmPelsHeight),
+            ));
+        }
+        let devmode = DevModeBuilder::new().refresh_rate(hz).build();
+        change_display_settings_wide(&wide_name, &devmode, persist_flags(persist))
+    }
+
+    /// Moves the monitor's top-left corner to `(x, y)` on the virtual desktop, leaving the primary
+    /// monitor as it is.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if this would move the primary monitor off the
+    /// origin, overlap another monitor, or leave a monitor cut off from the rest of the desktop.
+    pub fn set_position(&self, x: i32, y: i32, persist: bool) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        let config = DisplayConfig::capture()?;
+        let mut rects = Vec::with_capacity(config.monitors.len());
+        for monitor in &config.monitors {
+            let is_self = monitor.device_name == name;
+            if is_self && monitor.primary && (x, y) != (0, 0) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}: the primary monitor must stay at (0, 0)", name),
+                ));
+            }
+            let position = if is_self { PhysicalPosition::new(x, y) } else { monitor.position };
+            rects.push(RECT {
+                left: position.x,
+                top: position.y,
+                right: position.x + monitor.size.width as i32,
+                bottom: position.y + monitor.size.height as i32,
+            });
+        }
+        if !layout_is_contiguous(&rects) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: ({}, {}) would overlap another monitor or leave a gap in the desktop", name, x, y),
+            ));
+        }
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let devmode = DevModeBuilder::new().position(x, y).build();
+        change_display_settings_wide(&wide_name, &devmode, persist_flags(persist))
+    }
+
+    /// Checks with `CDS_TEST`, without changing anything, whether `set_mode` would work.
+    pub fn test_mode(&self, width: u32, height: u32) -> io::Result<DisplayChangeStatus> {
+        if !self.supports_mode(width, height, None) {
+            return Ok(DisplayChangeStatus::BadMode);
+        }
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let devmode = DevModeBuilder::new().width(width).height(height).build();
+        Ok(test_display_settings_wide(&wide_name, &devmode))
+    }
+
+    /// The current refresh rate alongside the best available, from a single mode enumeration.
+    pub fn refresh_summary(&self) -> Option<RefreshSummary> {
+        let current = DisplayMode::from(&get_dev_mode_wide(&self.wide_name()?).ok()?);
+        let modes = self.supported_modes();
+        let max_at_current_res = modes
+            .iter()
+            .filter(|mode| mode.width == current.width && mode.height == current.height)
+            .map(|mode| mode.refresh_rate)
+            .max()
+            .unwrap_or(current.refresh_rate);
+        let max_overall = modes.iter().map(|mode| mode.refresh_rate).max().unwrap_or(current.refresh_rate);
+        Some(RefreshSummary {
+            current: current.refresh_rate,
+            max_at_current_res: max_at_current_res.max(current.refresh_rate),
+            max_overall: max_overall.max(current.refresh_rate),
+        })
+    }
+
+    /// Whether the monitor supports `width` x `height`, at `refresh_rate` Hz if given, at any bit
+    /// depth.
+    pub fn supports_mode(&self, width: u32, height: u32, refresh_rate: Option<u32>) -> bool {
+        self.supported_modes().iter().any(|mode| {
+            mode.width == width && mode.height == height && refresh_rate.is_none_or(|hz| mode.refresh_rate == hz)
+        })
+    }
+}
+
+
+/// Iterates over the attached monitors.
+///
+/// `EnumDisplayMonitors` reports monitors through a callback that can't be suspended, so the
+/// handles are collected up front; the iterator only saves callers from building their own
+/// collection. Everything else about a monitor is queried lazily, so short-circuiting (e.g. with
+/// `find`) still skips the per-monitor work.
+pub fn monitors() -> impl Iterator<Item = MonitorHandle> {
     let mut monitors: VecDeque<MonitorHandle> = VecDeque::new();
     unsafe {
         EnumDisplayMonitors(
@@ -122,7 +708,90 @@ pub fn available_monitors() -> VecDeque<MonitorHandle> {
             &mut monitors as *mut _ as LPARAM,
         );
     }
-    monitors
+    monitors.into_iter()
+}
+
+pub fn available_monitors() -> VecDeque<MonitorHandle> {
+    monitors().collect()
+}
+
+/// A GDI device name such as `\\.\DISPLAY1`, kept alongside the NUL-terminated UTF-16 form the
+/// `*W` functions take so it is only encoded once. A bare `DISPLAY1` gets the `\\.\` prefix added.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceName {
+    name: String,
+    wide: Vec<u16>,
+}
+
+impl DeviceName {
+    const PREFIX: &'static str = r"\\.\";
+
+    pub fn new(name: &str) -> Self {
+        let name = if name.starts_with(Self::PREFIX) {
+            name.to_string()
+        } else {
+            format!("{}{}", Self::PREFIX, name)
+        };
+        let wide = wide_string(&name);
+        DeviceName { name, wide }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// The name as UTF-16, NUL-terminated.
+    #[inline]
+    pub fn as_wide(&self) -> &[u16] {
+        &self.wide
+    }
+}
+
+impl std::fmt::Display for DeviceName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl From<&str> for DeviceName {
+    fn from(name: &str) -> Self {
+        DeviceName::new(name)
+    }
+}
+
+impl From<&String> for DeviceName {
+    fn from(name: &String) -> Self {
+        DeviceName::new(name)
+    }
+}
+
+impl From<String> for DeviceName {
+    fn from(name: String) -> Self {
+        DeviceName::new(&name)
+    }
+}
+
+impl From<&DeviceName> for DeviceName {
+    fn from(name: &DeviceName) -> Self {
+        name.clone()
+    }
+}
+
+/// The attached monitor whose device name (e.g. `\\.\DISPLAY1`) is `display_name`.
+pub fn monitor_by_name(display_name: impl Into<DeviceName>) -> Option<MonitorHandle> {
+    let display_name = display_name.into();
+    monitors().find(|monitor| monitor.wide_name().as_deref() == Some(display_name.as_wide()))
+}
+
+/// The attached monitor named `\\.\DISPLAY{number}`.
+pub fn monitor_by_number(number: u32) -> Option<MonitorHandle> {
+    monitors().find(|monitor| monitor.display_number() == Some(number))
+}
+
+fn display_number_from_name(name: &str) -> Option<u32> {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    name[name.len() - digits..].parse().ok()
 }
 
 pub(crate) fn get_monitor_info(hmonitor: HMONITOR) -> Result<MONITORINFOEXW, io::Error> {
@@ -138,6 +807,14 @@ pub(crate) fn get_monitor_info(hmonitor: HMONITOR) -> Result<MONITORINFOEXW, io:
     }
 }
 
+/// `szDevice` up to its NUL, NUL-terminated again.
+fn wide_device_name(monitor_info: &MONITORINFOEXW) -> Vec<u16> {
+    let len = monitor_info.szDevice.iter().position(|c| *c == 0).unwrap_or(monitor_info.szDevice.len());
+    let mut wide_name = monitor_info.szDevice[..len].to_vec();
+    wide_name.push(0);
+    wide_name
+}
+
 pub fn decode_wide(mut wide_c_string: &[u16]) -> OsString {
     if let Some(null_pos) = wide_c_string.iter().position(|c| *c == 0) {
         wide_c_string = &wide_c_string[..null_pos];
@@ -201,13 +878,51 @@ impl<T> Deref for Lazy<T> {
 pub(crate) static GET_DPI_FOR_MONITOR: Lazy<Option<GetDpiForMonitor>> =
     Lazy::new(|| get_function!("shcore.dll", GetDpiForMonitor));
 
+/// The kinds of DPI `GetDpiForMonitor` can report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DpiType {
+    /// The DPI after the user's scaling setting is applied; what `scale_factor` is based on.
+    Effective,
+    /// The DPI that keeps the angular size of content constant at a typical viewing distance.
+    Angular,
+    /// The physical pixel density of the panel.
+    Raw,
+}
+
+impl DpiType {
+    fn mdt(self) -> MONITOR_DPI_TYPE {
+        match self {
+            DpiType::Effective => MDT_EFFECTIVE_DPI,
+            DpiType::Angular => MDT_ANGULAR_DPI,
+            DpiType::Raw => MDT_RAW_DPI,
+        }
+    }
+}
+
+impl FromStr for DpiType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "effective" => Ok(DpiType::Effective),
+            "angular" => Ok(DpiType::Angular),
+            "raw" => Ok(DpiType::Raw),
+            _ => Err(format!("invalid DPI type {:?}; expected 'effective', 'angular' or 'raw'", s)),
+        }
+    }
+}
+
 pub fn get_monitor_dpi(hmonitor: HMONITOR) -> Option<u32> {
+    get_monitor_dpi_for_type(hmonitor, DpiType::Effective)
+}
+
+pub fn get_monitor_dpi_for_type(hmonitor: HMONITOR, dpi_type: DpiType) -> Option<u32> {
     unsafe {
         if let Some(GetDpiForMonitor) = *GET_DPI_FOR_MONITOR {
             // We are on Windows 8.1 or later.
             let mut dpi_x = 0;
             let mut dpi_y = 0;
-            if GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) == S_OK {
+            if GetDpiForMonitor(hmonitor, dpi_type.mdt(), &mut dpi_x, &mut dpi_y) == S_OK {
                 // MSDN says that "the values of *dpiX and *dpiY are identical. You only need to
                 // record one of the values to determine the DPI and respond appropriately".
                 // https://msdn.microsoft.com/en-us/library/windows/desktop/dn280510(v=vs.85).aspx
@@ -219,228 +934,5093 @@ pub fn get_monitor_dpi(hmonitor: HMONITOR) -> Option<u32> {
 }
 
 
-pub fn primary_monitor() -> MonitorHandle {
-    const ORIGIN: POINT = POINT { x: 0, y: 0 };
-    let hmonitor = unsafe { MonitorFromPoint(ORIGIN, MONITOR_DEFAULTTOPRIMARY) };
-    MonitorHandle::new(hmonitor)
-}
+pub type GetThreadDpiAwarenessContext = unsafe extern "system" fn() -> DPI_AWARENESS_CONTEXT;
+pub type GetAwarenessFromDpiAwarenessContext =
+    unsafe extern "system" fn(value: DPI_AWARENESS_CONTEXT) -> DPI_AWARENESS;
+pub type AreDpiAwarenessContextsEqual =
+    unsafe extern "system" fn(dpi_context_a: DPI_AWARENESS_CONTEXT, dpi_context_b: DPI_AWARENESS_CONTEXT) -> BOOL;
+pub type GetProcessDpiAwareness =
+    unsafe extern "system" fn(hprocess: HANDLE, value: *mut PROCESS_DPI_AWARENESS) -> HRESULT;
+pub type GetDpiForSystem = unsafe extern "system" fn() -> u32;
+pub type SetProcessDpiAwarenessContext = unsafe extern "system" fn(value: DPI_AWARENESS_CONTEXT) -> BOOL;
+pub type SetThreadDpiAwarenessContext =
+    unsafe extern "system" fn(dpi_context: DPI_AWARENESS_CONTEXT) -> DPI_AWARENESS_CONTEXT;
 
-pub fn current_monitor(hwnd: HWND) -> MonitorHandle {
-    let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
-    MonitorHandle::new(hmonitor)
-}
+pub(crate) static GET_THREAD_DPI_AWARENESS_CONTEXT: Lazy<Option<GetThreadDpiAwarenessContext>> =
+    Lazy::new(|| get_function!("user32.dll", GetThreadDpiAwarenessContext));
+pub(crate) static GET_AWARENESS_FROM_DPI_AWARENESS_CONTEXT: Lazy<Option<GetAwarenessFromDpiAwarenessContext>> =
+    Lazy::new(|| get_function!("user32.dll", GetAwarenessFromDpiAwarenessContext));
+pub(crate) static ARE_DPI_AWARENESS_CONTEXTS_EQUAL: Lazy<Option<AreDpiAwarenessContextsEqual>> =
+    Lazy::new(|| get_function!("user32.dll", AreDpiAwarenessContextsEqual));
+pub(crate) static GET_PROCESS_DPI_AWARENESS: Lazy<Option<GetProcessDpiAwareness>> =
+    Lazy::new(|| get_function!("shcore.dll", GetProcessDpiAwareness));
+pub(crate) static GET_DPI_FOR_SYSTEM: Lazy<Option<GetDpiForSystem>> =
+    Lazy::new(|| get_function!("user32.dll", GetDpiForSystem));
+pub(crate) static SET_PROCESS_DPI_AWARENESS_CONTEXT: Lazy<Option<SetProcessDpiAwarenessContext>> =
+    Lazy::new(|| get_function!("user32.dll", SetProcessDpiAwarenessContext));
+pub(crate) static SET_THREAD_DPI_AWARENESS_CONTEXT: Lazy<Option<SetThreadDpiAwarenessContext>> =
+    Lazy::new(|| get_function!("user32.dll", SetThreadDpiAwarenessContext));
 
-unsafe extern "system" fn monitor_enum_proc(
-    hmonitor: HMONITOR,
-    _hdc: HDC,
-    _place: *mut RECT,
-    data: LPARAM,
-) -> BOOL {
-    let monitors = data as *mut VecDeque<MonitorHandle>;
-    unsafe { (*monitors).push_back(MonitorHandle::new(hmonitor)) };
-    true.into() // continue enumeration
+/// How the calling thread's coordinates and DPI values are virtualized by Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DpiAwareness {
+    Unaware,
+    SystemAware,
+    PerMonitorAware,
+    PerMonitorAwareV2,
 }
 
+impl DpiAwareness {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DpiAwareness::Unaware => "unaware",
+            DpiAwareness::SystemAware => "system_aware",
+            DpiAwareness::PerMonitorAware => "per_monitor_aware",
+            DpiAwareness::PerMonitorAwareV2 => "per_monitor_aware_v2",
+        }
+    }
 
-// Python bindings
-
-#[pyclass(module = "wmutil")]
-#[derive(Clone)]
-struct Monitor {
-    monitor_handle: MonitorHandle,
+    fn context(self) -> DPI_AWARENESS_CONTEXT {
+        match self {
+            DpiAwareness::Unaware => DPI_AWARENESS_CONTEXT_UNAWARE,
+            DpiAwareness::SystemAware => DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
+            DpiAwareness::PerMonitorAware => DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE,
+            DpiAwareness::PerMonitorAwareV2 => DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+        }
+    }
 }
 
-#[pymethods]
-impl Monitor {
-    #[getter]
-    fn name(&self) -> String {
-        self.monitor_handle.name().unwrap_or(String::from("Unknown monitor name"))
+impl FromStr for DpiAwareness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "unaware" => Ok(DpiAwareness::Unaware),
+            "system_aware" => Ok(DpiAwareness::SystemAware),
+            "per_monitor_aware" => Ok(DpiAwareness::PerMonitorAware),
+            "per_monitor_aware_v2" => Ok(DpiAwareness::PerMonitorAwareV2),
+            _ => Err(format!(
+                "invalid DPI awareness {:?}; expected 'unaware', 'system_aware', 'per_monitor_aware' or 'per_monitor_aware_v2'",
+                s
+            )),
+        }
     }
+}
 
-    #[getter]
-    fn size(&self) -> (u32, u32) {
-        let size = self.monitor_handle.size();
+/// The DPI awareness of the calling thread, which is the process's awareness unless the thread
+/// has overridden it.
+pub fn current_dpi_awareness() -> DpiAwareness {
+    unsafe {
+        if let (Some(get_context), Some(get_awareness), Some(contexts_equal)) = (
+            *GET_THREAD_DPI_AWARENESS_CONTEXT,
+            *GET_AWARENESS_FROM_DPI_AWARENESS_CONTEXT,
+            *ARE_DPI_AWARENESS_CONTEXTS_EQUAL,
+        ) {
+            // We are on Windows 10 1607 or later.
+            let context = get_context();
+            if contexts_equal(context, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) != false.into() {
+                return DpiAwareness::PerMonitorAwareV2;
+            }
+            return match get_awareness(context) {
+                DPI_AWARENESS_SYSTEM_AWARE => DpiAwareness::SystemAware,
+                DPI_AWARENESS_PER_MONITOR_AWARE => DpiAwareness::PerMonitorAware,
+                _ => DpiAwareness::Unaware,
+            };
+        }
+        if let Some(get_process_awareness) = *GET_PROCESS_DPI_AWARENESS {
+            // We are on Windows 8.1 or later.
+            let mut awareness = PROCESS_DPI_UNAWARE;
+            if get_process_awareness(0, &mut awareness) == S_OK {
+                return match awareness {
+                    PROCESS_SYSTEM_DPI_AWARE => DpiAwareness::SystemAware,
+                    PROCESS_PER_MONITOR_DPI_AWARE => DpiAwareness::PerMonitorAware,
+                    _ => DpiAwareness::Unaware,
+                };
+            }
+        }
+        if IsProcessDPIAware() != false.into() {
+            DpiAwareness::SystemAware
+        } else {
+            DpiAwareness::Unaware
+        }
+    }
+}
+
+/// Sets the DPI awareness of the whole process. Windows only lets this be set once, and fails if
+/// the manifest or an earlier call already chose one. Returns `Ok(false)` without changing anything
+/// on versions older than Windows 10 1703.
+pub fn set_process_dpi_awareness(awareness: DpiAwareness) -> io::Result<bool> {
+    let Some(set_context) = *SET_PROCESS_DPI_AWARENESS_CONTEXT else {
+        tracing::warn!("SetProcessDpiAwarenessContext is unavailable; DPI awareness left unchanged");
+        return Ok(false);
+    };
+    if unsafe { set_context(awareness.context()) } == false.into() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(true)
+}
+
+/// Restores the calling thread's previous DPI awareness when dropped. It is neither `Send` nor
+/// `Sync`, since dropping it on another thread would reset that thread's awareness instead.
+#[derive(Debug)]
+pub struct DpiAwarenessGuard {
+    previous: DPI_AWARENESS_CONTEXT,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for DpiAwarenessGuard {
+    fn drop(&mut self) {
+        if let Some(set_context) = *SET_THREAD_DPI_AWARENESS_CONTEXT {
+            unsafe { set_context(self.previous) };
+        }
+    }
+}
+
+/// Overrides the DPI awareness of the calling thread until the returned guard is dropped. Unlike
+/// the process awareness this can be changed any number of times, so it's the way to temporarily
+/// get unvirtualized sizes and DPI values. Returns `Ok(None)` without changing anything on versions
+/// older than Windows 10 1607.
+pub fn set_thread_dpi_awareness(awareness: DpiAwareness) -> io::Result<Option<DpiAwarenessGuard>> {
+    let Some(set_context) = *SET_THREAD_DPI_AWARENESS_CONTEXT else {
+        tracing::warn!("SetThreadDpiAwarenessContext is unavailable; DPI awareness left unchanged");
+        return Ok(None);
+    };
+    let previous = unsafe { set_context(awareness.context()) };
+    if previous == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("DPI awareness {:?} isn't supported by this version of Windows", awareness.as_str()),
+        ));
+    }
+    Ok(Some(DpiAwarenessGuard { previous, _not_send: PhantomData }))
+}
+
+/// The system DPI, i.e. the DPI of the primary monitor when the user signed in.
+pub fn system_dpi() -> u32 {
+    unsafe {
+        if let Some(get_dpi_for_system) = *GET_DPI_FOR_SYSTEM {
+            return get_dpi_for_system();
+        }
+        let hdc = GetDC(0);
+        if hdc == 0 {
+            return BASE_DPI;
+        }
+        let dpi = GetDeviceCaps(hdc, LOGPIXELSX as i32);
+        ReleaseDC(0, hdc);
+        dpi as u32
+    }
+}
+
+/// Every DPI value Windows reports for one monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorDpi {
+    pub monitor: MonitorHandle,
+    pub effective: Option<u32>,
+    pub angular: Option<u32>,
+    pub raw: Option<u32>,
+}
+
+/// A dump of the DPI environment, for troubleshooting windows that come out the wrong size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpiDiagnostics {
+    pub awareness: DpiAwareness,
+    pub system_dpi: u32,
+    pub monitors: Vec<MonitorDpi>,
+}
+
+pub fn dpi_diagnostics() -> DpiDiagnostics {
+    let monitors = available_monitors()
+        .into_iter()
+        .map(|monitor| MonitorDpi {
+            effective: get_monitor_dpi_for_type(monitor.0, DpiType::Effective),
+            angular: get_monitor_dpi_for_type(monitor.0, DpiType::Angular),
+            raw: get_monitor_dpi_for_type(monitor.0, DpiType::Raw),
+            monitor,
+        })
+        .collect();
+    DpiDiagnostics {
+        awareness: current_dpi_awareness(),
+        system_dpi: system_dpi(),
+        monitors,
+    }
+}
+
+
+pub fn primary_monitor() -> MonitorHandle {
+    const ORIGIN: POINT = POINT { x: 0, y: 0 };
+    let hmonitor = unsafe { MonitorFromPoint(ORIGIN, MONITOR_DEFAULTTOPRIMARY) };
+    MonitorHandle::new(hmonitor)
+}
+
+/// What `monitor_from_point` returns for a point that isn't on any monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MonitorFallback {
+    Primary,
+    Nearest,
+    None,
+}
+
+impl MonitorFallback {
+    fn flag(self) -> MONITOR_FROM_FLAGS {
+        match self {
+            MonitorFallback::Primary => MONITOR_DEFAULTTOPRIMARY,
+            MonitorFallback::Nearest => MONITOR_DEFAULTTONEAREST,
+            MonitorFallback::None => MONITOR_DEFAULTTONULL,
+        }
+    }
+}
+
+impl FromStr for MonitorFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "primary" => Ok(MonitorFallback::Primary),
+            "nearest" => Ok(MonitorFallback::Nearest),
+            "none" => Ok(MonitorFallback::None),
+            _ => Err(format!("invalid default {:?}; expected 'primary', 'nearest' or 'none'", s)),
+        }
+    }
+}
+
+/// The monitor containing (`x`, `y`). Only returns `None` with `MonitorFallback::None`.
+pub fn monitor_from_point(x: i32, y: i32, fallback: MonitorFallback) -> Option<MonitorHandle> {
+    let hmonitor = unsafe { MonitorFromPoint(POINT { x, y }, fallback.flag()) };
+    if hmonitor == 0 {
+        None
+    } else {
+        Some(MonitorHandle::new(hmonitor))
+    }
+}
+
+pub fn current_monitor(hwnd: HWND) -> MonitorHandle {
+    let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    MonitorHandle::new(hmonitor)
+}
+
+/// The monitor under the mouse cursor, or the nearest one if the cursor is off-screen. Falls back
+/// to the primary monitor if the cursor position can't be read (e.g. on the secure desktop).
+pub fn monitor_from_cursor() -> MonitorHandle {
+    let mut point = POINT { x: 0, y: 0 };
+    if unsafe { GetCursorPos(&mut point) } == false.into() {
+        return primary_monitor();
+    }
+    let hmonitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+    MonitorHandle::new(hmonitor)
+}
+
+/// The monitor `hwnd` would be maximized onto and the rectangle it would fill there.
+pub fn maximized_bounds_for_window(hwnd: HWND) -> (MonitorHandle, RECT) {
+    let monitor = current_monitor(hwnd);
+    let work_area = monitor.work_rect();
+    (monitor, work_area)
+}
+
+/// The monitor that contains the most of `points`.
+///
+/// Ties, and the case where no point lies on any monitor, are settled by the monitor nearest to
+/// the points' centroid. Returns `None` when `points` is empty.
+pub fn monitor_for_points(points: &[(i32, i32)]) -> Option<MonitorHandle> {
+    if points.is_empty() {
+        return None;
+    }
+    let mut counts: HashMap<HMONITOR, usize> = HashMap::new();
+    for &(x, y) in points {
+        let hmonitor = unsafe { MonitorFromPoint(POINT { x, y }, MONITOR_DEFAULTTONULL) };
+        if hmonitor != 0 {
+            *counts.entry(hmonitor).or_default() += 1;
+        }
+    }
+
+    let count = points.len() as i64;
+    let centroid = POINT {
+        x: (points.iter().map(|&(x, _)| x as i64).sum::<i64>() / count) as i32,
+        y: (points.iter().map(|&(_, y)| y as i64).sum::<i64>() / count) as i32,
+    };
+    let centroid_monitor = unsafe { MonitorFromPoint(centroid, MONITOR_DEFAULTTONEAREST) };
+
+    let best = counts.values().copied().max().unwrap_or(0);
+    let leaders: Vec<HMONITOR> = counts
+        .into_iter()
+        .filter(|&(_, points_on_monitor)| points_on_monitor == best)
+        .map(|(hmonitor, _)| hmonitor)
+        .collect();
+    let hmonitor = match leaders.as_slice() {
+        [only] => *only,
+        _ => centroid_monitor,
+    };
+    Some(MonitorHandle::new(hmonitor))
+}
+
+/// Groups the attached monitors by their current refresh rate in millihertz. Monitors whose
+/// refresh rate can't be queried are left out.
+pub fn refresh_rate_groups() -> HashMap<u32, Vec<MonitorHandle>> {
+    let mut groups: HashMap<u32, Vec<MonitorHandle>> = HashMap::new();
+    for monitor in available_monitors() {
+        if let Some(refresh_rate) = monitor.refresh_rate_millihertz() {
+            groups.entry(refresh_rate).or_default().push(monitor);
+        }
+    }
+    groups
+}
+
+/// Whether every attached monitor runs at the same refresh rate.
+pub fn refresh_rates_uniform() -> bool {
+    refresh_rate_groups().len() <= 1
+}
+
+/// Whether the process is running in a Remote Desktop session, where the displays are virtual.
+pub fn is_remote_session() -> bool {
+    unsafe { GetSystemMetrics(SM_REMOTESESSION) != 0 }
+}
+
+/// A display power state for `set_monitor_power`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MonitorPower {
+    On,
+    Off,
+    /// Low power; not every display distinguishes this from `Off`.
+    Standby,
+}
+
+impl MonitorPower {
+    /// The `lParam` that `SC_MONITORPOWER` expects.
+    fn lparam(self) -> LPARAM {
+        match self {
+            MonitorPower::On => -1,
+            MonitorPower::Standby => 1,
+            MonitorPower::Off => 2,
+        }
+    }
+}
+
+impl FromStr for MonitorPower {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "on" => Ok(MonitorPower::On),
+            "off" => Ok(MonitorPower::Off),
+            "standby" => Ok(MonitorPower::Standby),
+            _ => Err(format!("invalid power state {:?}; expected 'on', 'off' or 'standby'", s)),
+        }
+    }
+}
+
+/// Changes the power state of the displays by broadcasting `SC_MONITORPOWER`.
+///
+/// Windows applies this to every display at once; there is no per-monitor variant. Turning the
+/// displays off is undone by any mouse or keyboard input as well as by `MonitorPower::On`.
+pub fn set_monitor_power(state: MonitorPower) -> io::Result<()> {
+    // Posted rather than sent so a hung top-level window can't block the caller.
+    let posted = unsafe { PostMessageW(HWND_BROADCAST, WM_SYSCOMMAND, SC_MONITORPOWER as WPARAM, state.lparam()) };
+    if posted == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn monitor_enum_proc(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _place: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    let monitors = data as *mut VecDeque<MonitorHandle>;
+    unsafe { (*monitors).push_back(MonitorHandle::new(hmonitor)) };
+    true.into() // continue enumeration
+}
+
+
+// Layout
+
+/// Width of `layout_diagram`'s output in characters.
+const DIAGRAM_COLUMNS: i64 = 64;
+
+/// The smallest rectangle containing every rectangle in `rects`.
+fn bounding_rect(rects: &[RECT]) -> Option<RECT> {
+    rects.iter().copied().reduce(|bounds, rect| RECT {
+        left: bounds.left.min(rect.left),
+        top: bounds.top.min(rect.top),
+        right: bounds.right.max(rect.right),
+        bottom: bounds.bottom.max(rect.bottom),
+    })
+}
+
+/// Every attached monitor paired with its `rcMonitor`.
+fn monitors_with_rects() -> Vec<(MonitorHandle, RECT)> {
+    available_monitors()
+        .into_iter()
+        .filter_map(|monitor| {
+            let rect = get_monitor_info(monitor.0).ok()?.monitorInfo.rcMonitor;
+            Some((monitor, rect))
+        })
+        .collect()
+}
+
+/// The `rcMonitor` of every attached monitor.
+fn monitor_rects() -> Vec<RECT> {
+    monitors_with_rects().into_iter().map(|(_, rect)| rect).collect()
+}
+
+/// The bounding box of every monitor's `rcMonitor`, gaps and negative offsets included. Falls back
+/// to the `SM_*VIRTUALSCREEN` system metrics if no monitor could be queried.
+pub fn virtual_screen_bounds() -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let bounds = bounding_rect(&monitor_rects()).unwrap_or_else(|| unsafe {
+        let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        RECT {
+            left,
+            top,
+            right: left + GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            bottom: top + GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        }
+    });
+    (
+        PhysicalPosition { x: bounds.left, y: bounds.top },
+        PhysicalSize {
+            width: (bounds.right - bounds.left) as u32,
+            height: (bounds.bottom - bounds.top) as u32,
+        },
+    )
+}
+
+/// The monitor containing pixel (`vx`, `vy`) of an image of the whole virtual desktop, where
+/// (0, 0) is the top-left corner of the desktop's bounding box rather than the primary monitor's
+/// origin. Returns `None` for pixels outside every monitor, such as gaps in an L-shaped desktop.
+pub fn monitor_at_virtual_pixel(vx: i32, vy: i32) -> Option<MonitorHandle> {
+    let monitors = monitors_with_rects();
+    let rects: Vec<RECT> = monitors.iter().map(|(_, rect)| *rect).collect();
+    let bounds = bounding_rect(&rects)?;
+    let x = bounds.left.checked_add(vx)?;
+    let y = bounds.top.checked_add(vy)?;
+    monitors
+        .into_iter()
+        .find(|(_, rect)| rect.left <= x && x < rect.right && rect.top <= y && y < rect.bottom)
+        .map(|(monitor, _)| monitor)
+}
+
+/// The monitor sharing the most area with the rectangle at (`x`, `y`) of the given size, or the one
+/// nearest to it if the rectangle lies entirely off-screen. This is what `MonitorFromRect` does with
+/// `MONITOR_DEFAULTTONEAREST`.
+pub fn monitor_from_rect(x: i32, y: i32, width: u32, height: u32) -> MonitorHandle {
+    let rect = RECT {
+        left: x,
+        top: y,
+        right: x.saturating_add(width.min(i32::MAX as u32) as i32),
+        bottom: y.saturating_add(height.min(i32::MAX as u32) as i32),
+    };
+    let monitors = monitors_with_rects();
+    let overlapping = monitors
+        .iter()
+        .map(|(monitor, monitor_rect)| (monitor, intersection_area(&rect, monitor_rect)))
+        .filter(|&(_, area)| area > 0)
+        .max_by_key(|&(_, area)| area)
+        .map(|(monitor, _)| monitor.clone());
+    overlapping
+        .or_else(|| {
+            monitors
+                .into_iter()
+                .min_by_key(|(_, monitor_rect)| rect_distance_squared(&rect, monitor_rect))
+                .map(|(monitor, _)| monitor)
+        })
+        .unwrap_or_else(primary_monitor)
+}
+
+/// The monitors ordered left to right by their top-left corner, then top to bottom.
+pub fn monitors_by_position() -> Vec<MonitorHandle> {
+    let mut monitors = monitors_with_rects();
+    monitors.sort_by_key(|(_, rect)| (rect.left, rect.top));
+    monitors.into_iter().map(|(monitor, _)| monitor).collect()
+}
+
+/// Groups the monitors into rows of vertically overlapping monitors, top row first, each row
+/// ordered left to right.
+pub fn monitor_rows() -> Vec<Vec<MonitorHandle>> {
+    let monitors = monitors_with_rects();
+    let rects: Vec<RECT> = monitors.iter().map(|(_, rect)| *rect).collect();
+    rect_rows(&rects)
+        .into_iter()
+        .map(|row| row.into_iter().map(|index| monitors[index].0.clone()).collect())
+        .collect()
+}
+
+/// The indices of `rects` grouped like `monitor_rows`: a rectangle joins the current row if its
+/// top is above the bottom of every rectangle already in it.
+fn rect_rows(rects: &[RECT]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..rects.len()).collect();
+    order.sort_by_key(|&index| (rects[index].top, rects[index].left));
+    let mut rows: Vec<(i32, Vec<usize>)> = Vec::new();
+    for index in order {
+        let rect = &rects[index];
+        match rows.last_mut() {
+            Some((bottom, row)) if rect.top < *bottom => {
+                *bottom = (*bottom).max(rect.bottom);
+                row.push(index);
+            }
+            _ => rows.push((rect.bottom, vec![index])),
+        }
+    }
+    rows.into_iter()
+        .map(|(_, mut row)| {
+            row.sort_by_key(|&index| rects[index].left);
+            row
+        })
+        .collect()
+}
+
+/// Whether the monitors tile their bounding box with no gaps, i.e. the virtual desktop is a plain
+/// rectangle rather than, say, an L shape.
+pub fn desktop_is_rectangular() -> bool {
+    rects_tile_bounding_box(&monitor_rects())
+}
+
+/// Whether `rects` cover their bounding box completely. Overlaps are allowed.
+fn rects_tile_bounding_box(rects: &[RECT]) -> bool {
+    // Split the bounding box along every monitor edge; each resulting cell is either entirely
+    // covered by some monitor or entirely a gap.
+    let mut xs: Vec<i32> = rects.iter().flat_map(|rect| [rect.left, rect.right]).collect();
+    let mut ys: Vec<i32> = rects.iter().flat_map(|rect| [rect.top, rect.bottom]).collect();
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+    xs.windows(2).all(|x| {
+        ys.windows(2).all(|y| {
+            rects.iter().any(|rect| {
+                rect.left <= x[0] && x[1] <= rect.right && rect.top <= y[0] && y[1] <= rect.bottom
+            })
+        })
+    })
+}
+/// Whether two rectangles share some area.
+fn rects_overlap(a: &RECT, b: &RECT) -> bool {
+    a.left < b.right && b.left < a.right && a.top < b.bottom && b.top < a.bottom
+}
+
+/// The area two rectangles have in common.
+fn intersection_area(a: &RECT, b: &RECT) -> i64 {
+    let width = (a.right.min(b.right) as i64 - a.left.max(b.left) as i64).max(0);
+    let height = (a.bottom.min(b.bottom) as i64 - a.top.max(b.top) as i64).max(0);
+    width * height
+}
+
+/// The squared length of the shortest line between two rectangles, 0 if they touch or overlap.
+fn rect_distance_squared(a: &RECT, b: &RECT) -> i64 {
+    let dx = (a.left as i64 - b.right as i64).max(b.left as i64 - a.right as i64).max(0);
+    let dy = (a.top as i64 - b.bottom as i64).max(b.top as i64 - a.bottom as i64).max(0);
+    dx * dx + dy * dy
+}
+
+/// Whether two rectangles sit side by side or on top of each other with some length of edge in
+/// common. Touching only at a corner doesn't count.
+fn rects_share_edge(a: &RECT, b: &RECT) -> bool {
+    let vertical_overlap = a.top < b.bottom && b.top < a.bottom;
+    let horizontal_overlap = a.left < b.right && b.left < a.right;
+    ((a.right == b.left || b.right == a.left) && vertical_overlap)
+        || ((a.bottom == b.top || b.bottom == a.top) && horizontal_overlap)
+}
+
+/// Whether `rects` form one connected desktop without overlaps, which is what Windows requires of
+/// a monitor arrangement.
+fn layout_is_contiguous(rects: &[RECT]) -> bool {
+    for (index, a) in rects.iter().enumerate() {
+        if rects[index + 1..].iter().any(|b| rects_overlap(a, b)) {
+            return false;
+        }
+    }
+    if rects.is_empty() {
+        return true;
+    }
+    let mut reached = vec![false; rects.len()];
+    reached[0] = true;
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        for (other, rect) in rects.iter().enumerate() {
+            if !reached[other] && rects_share_edge(&rects[index], rect) {
+                reached[other] = true;
+                queue.push_back(other);
+            }
+        }
+    }
+    reached.into_iter().all(|reached| reached)
+}
+
+/// A direction on the virtual desktop, for moving between monitors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            _ => Err(format!("invalid direction {:?}; expected 'left', 'right', 'up' or 'down'", s)),
+        }
+    }
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+}
+
+/// The neighbor of `from` in `direction` among `rects`: the nearest one lying entirely on that
+/// side whose range on the other axis overlaps `from`'s. Ties go to the larger overlap.
+fn adjacent_rect(from: &RECT, direction: Direction, rects: &[RECT]) -> Option<usize> {
+    rects
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rect)| {
+            let (gap, overlap) = match direction {
+                Direction::Left => (from.left - rect.right, from.bottom.min(rect.bottom) - from.top.max(rect.top)),
+                Direction::Right => (rect.left - from.right, from.bottom.min(rect.bottom) - from.top.max(rect.top)),
+                Direction::Up => (from.top - rect.bottom, from.right.min(rect.right) - from.left.max(rect.left)),
+                Direction::Down => (rect.top - from.bottom, from.right.min(rect.right) - from.left.max(rect.left)),
+            };
+            (gap >= 0 && overlap > 0).then_some((gap, -overlap, index))
+        })
+        .min()
+        .map(|(_, _, index)| index)
+}
+
+/// Every monitor with its nearest aligned neighbor in each direction, worked out from the
+/// `rcMonitor` rectangles. Monitors don't have to touch; a gap between them is allowed.
+pub fn monitor_adjacency() -> Vec<(MonitorHandle, HashMap<Direction, MonitorHandle>)> {
+    let monitors = monitors_with_rects();
+    let rects: Vec<RECT> = monitors.iter().map(|(_, rect)| *rect).collect();
+    monitors
+        .iter()
+        .map(|(monitor, rect)| {
+            let neighbors = Direction::ALL
+                .into_iter()
+                .filter_map(|direction| {
+                    adjacent_rect(rect, direction, &rects).map(|index| (direction, monitors[index].0.clone()))
+                })
+                .collect();
+            (monitor.clone(), neighbors)
+        })
+        .collect()
+}
+
+/// The monitor nearest to `from` (center to center) whose center lies in `direction` of `from`'s
+/// center, or `None` if there is no monitor that way.
+pub fn monitor_in_direction(from: &MonitorHandle, direction: Direction) -> Option<MonitorHandle> {
+    let origin = from.center();
+    available_monitors()
+        .into_iter()
+        .filter(|monitor| monitor != from)
+        .filter_map(|monitor| {
+            let center = monitor.center();
+            let dx = (center.x - origin.x) as i64;
+            let dy = (center.y - origin.y) as i64;
+            let in_direction = match direction {
+                Direction::Left => dx < 0,
+                Direction::Right => dx > 0,
+                Direction::Up => dy < 0,
+                Direction::Down => dy > 0,
+            };
+            in_direction.then_some((dx * dx + dy * dy, monitor))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, monitor)| monitor)
+}
+
+fn draw_box(grid: &mut [Vec<char>], left: usize, top: usize, right: usize, bottom: usize, label: &str) {
+    grid[top][left..=right].fill('─');
+    grid[bottom][left..=right].fill('─');
+    for row in &mut grid[top..=bottom] {
+        row[left] = '│';
+        row[right] = '│';
+    }
+    grid[top][left] = '┌';
+    grid[top][right] = '┐';
+    grid[bottom][left] = '└';
+    grid[bottom][right] = '┘';
+    for (x, c) in (left + 1..right).zip(label.chars()) {
+        grid[top + 1][x] = c;
+    }
+}
+
+/// Renders the monitor arrangement as a box diagram, one box per monitor labeled with its index
+/// in `available_monitors` and its device name.
+pub fn layout_diagram() -> String {
+    let monitors: Vec<(RECT, String)> = monitors_with_rects()
+        .into_iter()
+        .map(|(monitor, rect)| (rect, monitor.name().unwrap_or_default()))
+        .collect();
+    render_layout_diagram(&monitors)
+}
+
+/// Draws `layout_diagram` from each monitor's `rcMonitor` and device name.
+fn render_layout_diagram(monitors: &[(RECT, String)]) -> String {
+    let rects: Vec<RECT> = monitors.iter().map(|(rect, _)| *rect).collect();
+    let Some(bounds) = bounding_rect(&rects) else {
+        return String::new();
+    };
+
+    // Pixels per column; rows cover twice as many pixels because characters are about twice as
+    // tall as they are wide.
+    let scale = ((bounds.right - bounds.left) as f64 / (DIAGRAM_COLUMNS - 1) as f64).max(1.0);
+    let to_column = |x: i32| ((x - bounds.left) as f64 / scale).round() as usize;
+    let to_row = |y: i32| ((y - bounds.top) as f64 / (scale * 2.0)).round() as usize;
+
+    let boxes: Vec<(usize, usize, usize, usize, String)> = monitors
+        .iter()
+        .enumerate()
+        .map(|(index, (rect, name))| {
+            let left = to_column(rect.left);
+            let top = to_row(rect.top);
+            // Keep every box at least big enough for a border and one line of label.
+            let right = to_column(rect.right).saturating_sub(1).max(left + 2);
+            let bottom = to_row(rect.bottom).saturating_sub(1).max(top + 2);
+            let label = format!("{}: {}", index, name.trim_start_matches(r"\\.\"));
+            (left, top, right, bottom, label)
+        })
+        .collect();
+
+    let columns = boxes.iter().map(|b| b.2).max().unwrap_or(0) + 1;
+    let rows = boxes.iter().map(|b| b.3).max().unwrap_or(0) + 1;
+    let mut grid = vec![vec![' '; columns]; rows];
+    for (left, top, right, bottom, label) in &boxes {
+        draw_box(&mut grid, *left, *top, *right, *bottom, label);
+    }
+    grid.iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+
+// QueryDisplayConfig / DisplayConfigGetDeviceInfo
+
+/// Returns the active paths and their modes as reported by `QueryDisplayConfig`.
+pub(crate) fn query_display_config() -> io::Result<(Vec<DISPLAYCONFIG_PATH_INFO>, Vec<DISPLAYCONFIG_MODE_INFO>)> {
+    loop {
+        let mut path_count = 0;
+        let mut mode_count = 0;
+        let status = unsafe { GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count) };
+        if status != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(status as i32));
+        }
+        let mut paths: Vec<DISPLAYCONFIG_PATH_INFO> = vec![unsafe { mem::zeroed() }; path_count as usize];
+        let mut modes: Vec<DISPLAYCONFIG_MODE_INFO> = vec![unsafe { mem::zeroed() }; mode_count as usize];
+        let status = unsafe {
+            QueryDisplayConfig(
+                QDC_ONLY_ACTIVE_PATHS,
+                &mut path_count,
+                paths.as_mut_ptr(),
+                &mut mode_count,
+                modes.as_mut_ptr(),
+                null_mut(),
+            )
+        };
+        match status {
+            ERROR_SUCCESS => {
+                paths.truncate(path_count as usize);
+                modes.truncate(mode_count as usize);
+                return Ok((paths, modes));
+            }
+            // The topology changed between the two calls; try again with fresh sizes.
+            ERROR_INSUFFICIENT_BUFFER => continue,
+            _ => return Err(io::Error::from_raw_os_error(status as i32)),
+        }
+    }
+}
+
+/// Issues a `DisplayConfigGetDeviceInfo` request. `T` must be one of the `DISPLAYCONFIG_*` packets
+/// that begin with a `DISPLAYCONFIG_DEVICE_INFO_HEADER`.
+pub(crate) unsafe fn display_config_device_info<T: Copy>(
+    info_type: DISPLAYCONFIG_DEVICE_INFO_TYPE,
+    adapter_id: LUID,
+    id: u32,
+) -> Option<T> {
+    let mut packet: T = mem::zeroed();
+    let header = &mut packet as *mut T as *mut DISPLAYCONFIG_DEVICE_INFO_HEADER;
+    (*header).r#type = info_type;
+    (*header).size = size_of::<T>() as u32;
+    (*header).adapterId = adapter_id;
+    (*header).id = id;
+    if DisplayConfigGetDeviceInfo(header) == ERROR_SUCCESS as i32 {
+        Some(packet)
+    } else {
+        None
+    }
+}
+
+/// Bits of `DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO`'s flags.
+const ADVANCED_COLOR_SUPPORTED: u32 = 1 << 0;
+const ADVANCED_COLOR_ENABLED: u32 = 1 << 1;
+
+/// The GDI device name (e.g. `\\.\DISPLAY1`) of a path's source.
+fn display_config_source_name(path: &DISPLAYCONFIG_PATH_INFO) -> Option<OsString> {
+    let source_name: DISPLAYCONFIG_SOURCE_DEVICE_NAME = unsafe {
+        display_config_device_info(
+            DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+            path.sourceInfo.adapterId,
+            path.sourceInfo.id,
+        )
+    }?;
+    Some(decode_wide(&source_name.viewGdiDeviceName))
+}
+
+impl MonitorHandle {
+    /// The active `QueryDisplayConfig` path whose source is this monitor. When the monitor is
+    /// cloned to several targets, the first one is returned.
+    pub(crate) fn display_config_path(&self) -> Option<DISPLAYCONFIG_PATH_INFO> {
+        let device_name = decode_wide(&get_monitor_info(self.0).ok()?.szDevice);
+        let (paths, _) = query_display_config().ok()?;
+        paths
+            .into_iter()
+            .find(|path| display_config_source_name(path).as_ref() == Some(&device_name))
+    }
+
+    /// The exact refresh rate of the monitor's display path as numerator and denominator, e.g.
+    /// `(60000, 1001)` for 59.94 Hz. Returns `None` if the monitor can't be matched to a path.
+    pub fn refresh_rate_rational(&self) -> Option<(u32, u32)> {
+        let rate = self.display_config_path()?.targetInfo.refreshRate;
+        if rate.Denominator == 0 {
+            None
+        } else {
+            Some((rate.Numerator, rate.Denominator))
+        }
+    }
+
+    /// How the monitor is connected, as one of the `DISPLAYCONFIG_OUTPUT_TECHNOLOGY_*` values.
+    pub(crate) fn output_technology(&self) -> Option<DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY> {
+        Some(self.display_config_path()?.targetInfo.outputTechnology)
+    }
+
+    /// Whether this is a built-in panel, such as a laptop screen. Returns `false` when the
+    /// connection type can't be determined.
+    pub fn is_internal(&self) -> bool {
+        self.output_technology().is_some_and(is_internal_output_technology)
+    }
+
+    /// A readable name for how the monitor is connected, such as "HDMI" or "DisplayPort", or
+    /// "Unknown" when it can't be determined.
+    pub fn connection_type(&self) -> &'static str {
+        self.output_technology().map_or("Unknown", output_technology_name)
+    }
+
+    pub(crate) fn advanced_color_info(&self) -> Option<DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO> {
+        let path = self.display_config_path()?;
+        unsafe {
+            display_config_device_info(
+                DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO,
+                path.targetInfo.adapterId,
+                path.targetInfo.id,
+            )
+        }
+    }
+
+    /// Whether the display is driven at fewer than 8 bits per color channel, which makes banding
+    /// in smooth gradients likely.
+    ///
+    /// This relies on the bit depth Windows reports through the advanced color info (Windows 10
+    /// 1703 and later). Panels that dither 6-bit output up to 8 bits (FRC) report 8 bits there and
+    /// can't be told apart from true 8-bit panels. Returns `false` when the bit depth is unknown.
+    pub fn is_low_bit_depth(&self) -> bool {
+        self.advanced_color_info()
+            .is_some_and(|info| info.bitsPerColorChannel != 0 && info.bitsPerColorChannel < 8)
+    }
+
+    /// Whether the display advertises advanced color (HDR). Returns `false` before Windows 10 1703
+    /// or when the monitor can't be matched to a display path.
+    pub fn hdr_supported(&self) -> bool {
+        self.advanced_color_info()
+            .is_some_and(|info| has_flag(unsafe { info.Anonymous.value }, ADVANCED_COLOR_SUPPORTED))
+    }
+
+    /// Whether HDR is currently turned on for the display.
+    pub fn hdr_enabled(&self) -> bool {
+        self.advanced_color_info()
+            .is_some_and(|info| has_flag(unsafe { info.Anonymous.value }, ADVANCED_COLOR_ENABLED))
+    }
+
+    /// Turns HDR on or off, like the "Use HDR" switch in the Settings app. Takes effect
+    /// immediately and is remembered by Windows.
+    pub fn set_hdr(&self, enabled: bool) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        if !self.hdr_supported() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("{}: HDR is not supported", name)));
+        }
+        let path = self
+            .display_config_path()
+            .ok_or_else(|| io::Error::other(format!("{}: no active display path", name)))?;
+        let request = DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE {
+            header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                r#type: DISPLAYCONFIG_DEVICE_INFO_SET_ADVANCED_COLOR_STATE,
+                size: size_of::<DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE>() as u32,
+                adapterId: path.targetInfo.adapterId,
+                id: path.targetInfo.id,
+            },
+            Anonymous: DISPLAYCONFIG_SET_ADVANCED_COLOR_STATE_0 { value: enabled as u32 },
+        };
+        let status = unsafe { DisplayConfigSetDeviceInfo(&request.header) };
+        if status == ERROR_SUCCESS as i32 {
+            Ok(())
+        } else {
+            Err(io::Error::from_raw_os_error(status))
+        }
+    }
+
+    /// The name the monitor reports for itself (e.g. "DELL U2720Q"), as shown in the Settings app.
+    /// Returns `None` when Windows has no name for it, which is common for generic PnP monitors.
+    pub fn friendly_name(&self) -> Option<String> {
+        let path = self.display_config_path()?;
+        let target_name: DISPLAYCONFIG_TARGET_DEVICE_NAME = unsafe {
+            display_config_device_info(
+                DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+                path.targetInfo.adapterId,
+                path.targetInfo.id,
+            )
+        }?;
+        let name = decode_wide(&target_name.monitorFriendlyDeviceName).to_string_lossy().into_owned();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+}
+
+
+// DDC/CI brightness
+//
+// Brightness goes over the monitor's DDC/CI channel through the dxva2 monitor configuration API.
+// Internal laptop panels and many external monitors don't implement it, and an HMONITOR can map to
+// several physical monitors when displays are duplicated; the first one that answers is used.
+
+/// The physical monitors behind an HMONITOR, destroyed on drop.
+struct PhysicalMonitors(Vec<PHYSICAL_MONITOR>);
+
+impl PhysicalMonitors {
+    fn from_hmonitor(hmonitor: HMONITOR) -> io::Result<Self> {
+        let mut count = 0;
+        if unsafe { GetNumberOfPhysicalMonitorsFromHMONITOR(hmonitor, &mut count) } == false.into() {
+            return Err(io::Error::last_os_error());
+        }
+        let mut monitors: Vec<PHYSICAL_MONITOR> = vec![unsafe { mem::zeroed() }; count as usize];
+        if count > 0
+            && unsafe { GetPhysicalMonitorsFromHMONITOR(hmonitor, count, monitors.as_mut_ptr()) } == false.into()
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(PhysicalMonitors(monitors))
+    }
+
+    fn handles(&self) -> impl Iterator<Item = HANDLE> + '_ {
+        self.0.iter().map(|monitor| monitor.hPhysicalMonitor)
+    }
+}
+
+impl Drop for PhysicalMonitors {
+    fn drop(&mut self) {
+        if !self.0.is_empty() {
+            unsafe { DestroyPhysicalMonitors(self.0.len() as u32, self.0.as_ptr()) };
+        }
+    }
+}
+
+fn physical_brightness(handle: HANDLE) -> Option<(u32, u32, u32)> {
+    let (mut min, mut current, mut max) = (0, 0, 0);
+    if unsafe { GetMonitorBrightness(handle, &mut min, &mut current, &mut max) } == 0 {
+        None
+    } else {
+        Some((min, current, max))
+    }
+}
+
+impl MonitorHandle {
+    /// The brightness as `(min, current, max)`, or `None` if the monitor doesn't support DDC/CI.
+    ///
+    /// DDC/CI is slow; expect each call to take tens of milliseconds.
+    pub fn get_brightness(&self) -> Option<(u32, u32, u32)> {
+        let physical = PhysicalMonitors::from_hmonitor(self.0).ok()?;
+        let brightness = physical.handles().find_map(physical_brightness);
+        brightness
+    }
+
+    /// Sets the brightness, which must lie within the range reported by `get_brightness`.
+    ///
+    /// Fails with `io::ErrorKind::Unsupported` if none of the physical monitors behind this one
+    /// support DDC/CI, and with `io::ErrorKind::InvalidInput` if `value` is out of range.
+    pub fn set_brightness(&self, value: u32) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        let physical = PhysicalMonitors::from_hmonitor(self.0)?;
+        let (handle, (min, _, max)) = physical
+            .handles()
+            .find_map(|handle| physical_brightness(handle).map(|brightness| (handle, brightness)))
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Unsupported, format!("{}: brightness can't be controlled over DDC/CI", name))
+            })?;
+        if !(min..=max).contains(&value) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: brightness must be between {} and {}, not {}", name, min, max, value),
+            ));
+        }
+        if unsafe { SetMonitorBrightness(handle, value) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+
+// Gamma ramps
+
+/// Red, green and blue lookup tables mapping each 8-bit input level to a 16-bit output level.
+pub type GammaRamp = [[u16; 256]; 3];
+
+/// The identity ramp, which leaves colors unchanged.
+pub fn linear_gamma_ramp() -> GammaRamp {
+    let mut channel = [0u16; 256];
+    for (level, value) in channel.iter_mut().enumerate() {
+        *value = (level as u16) << 8 | level as u16;
+    }
+    [channel; 3]
+}
+
+/// The color temperatures `set_color_temperature` accepts, in kelvin.
+pub const COLOR_TEMPERATURE_RANGE: std::ops::RangeInclusive<u32> = 1000..=10000;
+
+/// The whitepoint of a blackbody at `kelvin` as red, green and blue in `0..=255`, using Tanner
+/// Helland's curve fit.
+fn blackbody_rgb(kelvin: u32) -> [f64; 3] {
+    let temperature = kelvin as f64 / 100.0;
+    let red = if temperature <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (temperature - 60.0).powf(-0.1332047592)
+    };
+    let green = if temperature <= 66.0 {
+        99.4708025861 * temperature.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (temperature - 60.0).powf(-0.0755148492)
+    };
+    let blue = if temperature >= 66.0 {
+        255.0
+    } else if temperature <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (temperature - 10.0).ln() - 305.0447927307
+    };
+    [red, green, blue].map(|channel| channel.clamp(0.0, 255.0))
+}
+
+/// A gamma ramp that tints the display towards the color of a blackbody at `kelvin`, scaled so
+/// that 6500K leaves colors unchanged.
+pub fn color_temperature_ramp(kelvin: u32) -> GammaRamp {
+    let neutral = blackbody_rgb(6500);
+    let target = blackbody_rgb(kelvin);
+    let mut ramp = linear_gamma_ramp();
+    for (index, channel) in ramp.iter_mut().enumerate() {
+        let scale = (target[index] / neutral[index]).min(1.0);
+        for value in channel.iter_mut() {
+            *value = (*value as f64 * scale).round() as u16;
+        }
+    }
+    ramp
+}
+
+impl MonitorHandle {
+    /// Runs `f` with a device context for this monitor, deleting it afterwards.
+    fn with_display_dc<T>(&self, f: impl FnOnce(HDC) -> T) -> io::Result<T> {
+        let wide_name = self
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let driver = wide_string("DISPLAY");
+        let hdc = unsafe { CreateDCW(driver.as_ptr(), wide_name.as_ptr(), null(), null()) };
+        if hdc == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let result = f(hdc);
+        unsafe { DeleteDC(hdc) };
+        Ok(result)
+    }
+
+    pub fn get_gamma_ramp(&self) -> io::Result<GammaRamp> {
+        let mut ramp: GammaRamp = [[0; 256]; 3];
+        let ok = self.with_display_dc(|hdc| unsafe { GetDeviceGammaRamp(hdc, ramp.as_mut_ptr().cast()) })?;
+        if ok == false.into() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(ramp)
+    }
+
+    /// Loads `ramp` into the display hardware.
+    ///
+    /// Windows rejects ramps that stray too far from linear, and on some drivers the ramp only
+    /// sticks while the calling application has focus or is reset on the next mode change.
+    pub fn set_gamma_ramp(&self, ramp: &GammaRamp) -> io::Result<()> {
+        let ok = self.with_display_dc(|hdc| unsafe { SetDeviceGammaRamp(hdc, ramp.as_ptr().cast()) })?;
+        if ok == false.into() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Tints the display to a color temperature through the gamma ramp; 6500K is neutral and lower
+    /// values are warmer. Very warm values may be rejected by Windows' limits on gamma ramps.
+    pub fn set_color_temperature(&self, kelvin: u32) -> io::Result<()> {
+        if !COLOR_TEMPERATURE_RANGE.contains(&kelvin) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "color temperature must be between {}K and {}K, not {}K",
+                    COLOR_TEMPERATURE_RANGE.start(),
+                    COLOR_TEMPERATURE_RANGE.end(),
+                    kelvin
+                ),
+            ));
+        }
+        self.set_gamma_ramp(&color_temperature_ramp(kelvin))
+    }
+}
+
+
+// Per-monitor scaling
+//
+// Windows has no public API for the per-monitor scale percentage shown in the Settings app. The
+// two packet types below are the undocumented `DisplayConfigGetDeviceInfo` /
+// `DisplayConfigSetDeviceInfo` requests that the Settings app itself uses.
+
+const DISPLAYCONFIG_DEVICE_INFO_GET_DPI_SCALE: DISPLAYCONFIG_DEVICE_INFO_TYPE = -3;
+const DISPLAYCONFIG_DEVICE_INFO_SET_DPI_SCALE: DISPLAYCONFIG_DEVICE_INFO_TYPE = -4;
+
+/// The scale percentages Windows steps through. The scaling packets describe scales as offsets
+/// into this table relative to the monitor's recommended scale.
+pub const SCALE_PERCENT_STEPS: [u32; 12] = [100, 125, 150, 175, 200, 225, 250, 300, 350, 400, 450, 500];
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DisplayConfigSourceDpiScaleGet {
+    header: DISPLAYCONFIG_DEVICE_INFO_HEADER,
+    min_scale_rel: i32,
+    cur_scale_rel: i32,
+    max_scale_rel: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DisplayConfigSourceDpiScaleSet {
+    header: DISPLAYCONFIG_DEVICE_INFO_HEADER,
+    scale_rel: i32,
+}
+
+/// A monitor's scale percentage as configured in the Settings app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaleInfo {
+    pub current: u32,
+    pub recommended: u32,
+    /// The percentages the monitor accepts, in ascending order.
+    pub available: Vec<u32>,
+}
+
+impl MonitorHandle {
+    fn dpi_scale_packet(&self) -> Option<(DISPLAYCONFIG_PATH_INFO, DisplayConfigSourceDpiScaleGet)> {
+        let path = self.display_config_path()?;
+        let packet = unsafe {
+            display_config_device_info::<DisplayConfigSourceDpiScaleGet>(
+                DISPLAYCONFIG_DEVICE_INFO_GET_DPI_SCALE,
+                path.sourceInfo.adapterId,
+                path.sourceInfo.id,
+            )
+        }?;
+        Some((path, packet))
+    }
+
+    /// The monitor's current, recommended and available scale percentages. Uses an undocumented
+    /// interface, so this returns `None` if Windows doesn't answer it.
+    pub fn scale_info(&self) -> Option<ScaleInfo> {
+        let (_, packet) = self.dpi_scale_packet()?;
+        // The minimum offset is always at or below the recommended scale, which makes its
+        // magnitude the recommended scale's index.
+        let recommended = packet.min_scale_rel.unsigned_abs() as i32;
+        let step = |relative: i32| SCALE_PERCENT_STEPS.get(usize::try_from(recommended + relative).ok()?).copied();
+        Some(ScaleInfo {
+            current: step(packet.cur_scale_rel)?,
+            recommended: step(0)?,
+            available: (packet.min_scale_rel..=packet.max_scale_rel).filter_map(step).collect(),
+        })
+    }
+
+    /// Sets the monitor's scale percentage, which must be one of `scale_info().available`.
+    /// Uses an undocumented interface; see `scale_info`.
+    pub fn set_scale_percent(&self, percent: u32) -> io::Result<()> {
+        let name = self.name().unwrap_or_default();
+        let (path, packet) = self.dpi_scale_packet().ok_or_else(|| {
+            io::Error::other(format!(
+                "{}: scaling information is unavailable (per-monitor scaling relies on an undocumented \
+                 Windows interface that this version of Windows doesn't answer)",
+                name
+            ))
+        })?;
+        let recommended = packet.min_scale_rel.unsigned_abs() as i32;
+        let available = packet.min_scale_rel..=packet.max_scale_rel;
+        let relative = SCALE_PERCENT_STEPS
+            .iter()
+            .position(|&step| step == percent)
+            .map(|index| index as i32 - recommended)
+            .filter(|relative| available.contains(relative))
+            .ok_or_else(|| {
+                let supported = self.scale_info().map(|info| info.available).unwrap_or_default();
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}: {}% scaling is not supported; expected one of {:?}", name, percent, supported),
+                )
+            })?;
+
+        let request = DisplayConfigSourceDpiScaleSet {
+            header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                r#type: DISPLAYCONFIG_DEVICE_INFO_SET_DPI_SCALE,
+                size: size_of::<DisplayConfigSourceDpiScaleSet>() as u32,
+                adapterId: path.sourceInfo.adapterId,
+                id: path.sourceInfo.id,
+            },
+            scale_rel: relative,
+        };
+        let status = unsafe { DisplayConfigSetDeviceInfo(&request.header) };
+        if status == ERROR_SUCCESS as i32 {
+            Ok(())
+        } else {
+            Err(io::Error::from_raw_os_error(status))
+        }
+    }
+
+    /// Whether the monitor is at the scale percentage Windows recommends for it, or `None` if
+    /// `scale_info` is unavailable.
+    pub fn scale_is_recommended(&self) -> Option<bool> {
+        self.scale_info().map(|info| info.current == info.recommended)
+    }
+
+    /// Sets the monitor back to its recommended scale percentage.
+    pub fn reset_scale_to_recommended(&self) -> io::Result<()> {
+        let info = self.scale_info().ok_or_else(|| {
+            io::Error::other(format!("{}: scaling information is unavailable", self.name().unwrap_or_default()))
+        })?;
+        if info.current == info.recommended {
+            return Ok(());
+        }
+        self.set_scale_percent(info.recommended)
+    }
+}
+
+/// Sets every attached monitor to `percent` scaling, keyed by device name (or by handle, for a
+/// monitor whose name can't be read). Monitors that don't offer that step are skipped and reported
+/// as an error.
+pub fn set_all_scale_percent(percent: u32) -> HashMap<String, io::Result<()>> {
+    available_monitors()
+        .into_iter()
+        .map(|monitor| (monitor.result_key(), monitor.set_scale_percent(percent)))
+        .collect()
+}
+
+
+// EDID
+
+const EDID_HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+const EDID_SERIAL_NUMBER_TAG: u8 = 0xFF;
+
+/// Reads a text display descriptor (such as the serial number, tag `0xFF`) from the base EDID
+/// block.
+fn edid_descriptor_string(edid: &[u8], tag: u8) -> Option<String> {
+    if edid.len() < 128 || edid[..8] != EDID_HEADER {
+        return None;
+    }
+    for offset in [54, 72, 90, 108] {
+        let descriptor = &edid[offset..offset + 18];
+        // Display descriptors start with a zero pixel clock, which sets them apart from detailed
+        // timing descriptors.
+        if descriptor[..3] == [0, 0, 0] && descriptor[3] == tag {
+            let text = &descriptor[5..];
+            let end = text.iter().position(|&byte| byte == b'\n').unwrap_or(text.len());
+            let text = String::from_utf8_lossy(&text[..end]).trim().to_string();
+            return if text.is_empty() { None } else { Some(text) };
+        }
+    }
+    None
+}
+
+/// The manufacturer ID, product code and serial number from the EDID header as hex, which
+/// together identify a particular physical monitor.
+fn edid_product_id(edid: &[u8]) -> Option<String> {
+    Some(edid_header(edid)?[8..16].iter().map(|byte| format!("{:02X}", byte)).collect())
+}
+
+/// The 16-byte EDID header, or `None` if `edid` doesn't start with one.
+fn edid_header(edid: &[u8]) -> Option<&[u8]> {
+    if edid.len() < 16 || edid[..8] != EDID_HEADER {
+        return None;
+    }
+    Some(&edid[..16])
+}
+
+/// The three-letter PNP ID of the manufacturer (e.g. "DEL"), packed into the header as three
+/// big-endian 5-bit letters where 1 is 'A'.
+fn edid_manufacturer_id(edid: &[u8]) -> Option<String> {
+    let header = edid_header(edid)?;
+    let packed = u16::from_be_bytes([header[8], header[9]]);
+    [10, 5, 0]
+        .into_iter()
+        .map(|shift| match (packed >> shift) & 0x1F {
+            letter @ 1..=26 => Some((b'A' + letter as u8 - 1) as char),
+            _ => None,
+        })
+        .collect()
+}
+
+fn edid_product_code(edid: &[u8]) -> Option<u16> {
+    let header = edid_header(edid)?;
+    Some(u16::from_le_bytes([header[10], header[11]]))
+}
+
+/// The numeric serial number from the header, which many monitors leave as zero.
+fn edid_header_serial(edid: &[u8]) -> Option<u32> {
+    let header = edid_header(edid)?;
+    match u32::from_le_bytes([header[12], header[13], header[14], header[15]]) {
+        0 => None,
+        serial => Some(serial),
+    }
+}
+
+/// Maps a monitor device interface path such as
+/// `\\?\DISPLAY#DEL40F4#5&2d8f3a0b&0&UID4353#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}` to its
+/// `Device Parameters` key under `HKLM\SYSTEM\CurrentControlSet\Enum`.
+fn device_parameters_key(interface_path: &str) -> Option<String> {
+    let mut parts = interface_path.trim_start_matches(r"\\?\").split('#');
+    let (enumerator, model, instance) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(format!(r"SYSTEM\CurrentControlSet\Enum\{}\{}\{}\Device Parameters", enumerator, model, instance))
+}
+
+fn read_registry_binary(key: HKEY, subkey: &str, value: &str) -> Option<Vec<u8>> {
+    let subkey = wide_string(subkey);
+    let value = wide_string(value);
+    let mut size = 0u32;
+    let status = unsafe {
+        RegGetValueW(key, subkey.as_ptr(), value.as_ptr(), RRF_RT_REG_BINARY, null_mut(), null_mut(), &mut size)
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let mut data = vec![0u8; size as usize];
+    let status = unsafe {
+        RegGetValueW(
+            key,
+            subkey.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_BINARY,
+            null_mut(),
+            data.as_mut_ptr() as *mut c_void,
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    data.truncate(size as usize);
+    Some(data)
+}
+
+impl MonitorHandle {
+    /// The device interface path of the physical monitor attached to this display.
+    fn monitor_interface_path(&self) -> Option<String> {
+        let monitor_info = get_monitor_info(self.0).ok()?;
+        let mut device: DISPLAY_DEVICEW = unsafe { mem::zeroed() };
+        device.cb = size_of::<DISPLAY_DEVICEW>() as u32;
+        let found = unsafe {
+            EnumDisplayDevicesW(monitor_info.szDevice.as_ptr(), 0, &mut device, EDD_GET_DEVICE_INTERFACE_NAME)
+        };
+        if found == false.into() {
+            return None;
+        }
+        Some(decode_wide(&device.DeviceID).to_string_lossy().into_owned())
+    }
+
+    /// The name of the graphics adapter driving this display (e.g. "NVIDIA GeForce RTX 3080"),
+    /// or `None` if the driver doesn't report one.
+    pub fn adapter_name(&self) -> Option<String> {
+        let device = self.adapter_device()?;
+        let adapter_name = decode_wide(&device.DeviceString).to_string_lossy().trim().to_string();
+        if adapter_name.is_empty() {
+            None
+        } else {
+            Some(adapter_name)
+        }
+    }
+
+    /// The adapter output this display is attached to.
+    fn adapter_device(&self) -> Option<DISPLAY_DEVICEW> {
+        let device_name = decode_wide(&get_monitor_info(self.0).ok()?.szDevice);
+        let mut device: DISPLAY_DEVICEW = unsafe { mem::zeroed() };
+        device.cb = size_of::<DISPLAY_DEVICEW>() as u32;
+        let mut index = 0;
+        // With no device name, EnumDisplayDevicesW walks the adapter outputs, whose names are the
+        // same `\\.\DISPLAYn` names the monitors have.
+        while unsafe { EnumDisplayDevicesW(null(), index, &mut device, 0) } != false.into() {
+            if decode_wide(&device.DeviceName) == device_name {
+                return Some(device);
+            }
+            index += 1;
+        }
+        None
+    }
+
+    /// Whether this is a mirroring driver or a display that isn't part of the desktop, as some
+    /// remote desktop and screen capture software installs. Returns `false` if unsure.
+    pub fn is_pseudo(&self) -> bool {
+        self.adapter_device().is_some_and(|device| {
+            has_flag(device.StateFlags, DISPLAY_DEVICE_MIRRORING_DRIVER)
+                || !has_flag(device.StateFlags, DISPLAY_DEVICE_ATTACHED_TO_DESKTOP)
+        })
+    }
+
+    /// The raw EDID the monitor reported, as cached by Windows in the registry.
+    pub fn edid(&self) -> Option<Vec<u8>> {
+        let key = device_parameters_key(&self.monitor_interface_path()?)?;
+        read_registry_binary(HKEY_LOCAL_MACHINE, &key, "EDID")
+    }
+
+    /// The serial number string from the EDID's display descriptors, or the numeric serial number
+    /// from the EDID header if the monitor only provides that.
+    pub fn serial_number(&self) -> Option<String> {
+        let edid = self.edid()?;
+        edid_descriptor_string(&edid, EDID_SERIAL_NUMBER_TAG)
+            .or_else(|| edid_header_serial(&edid).map(|serial| serial.to_string()))
+    }
+
+    /// The manufacturer's three-letter PNP ID from the EDID, such as "DEL" or "SAM".
+    pub fn manufacturer_id(&self) -> Option<String> {
+        edid_manufacturer_id(&self.edid()?)
+    }
+
+    /// The manufacturer's product code from the EDID.
+    pub fn product_code(&self) -> Option<u16> {
+        edid_product_code(&self.edid()?)
+    }
+
+    /// An identifier that survives the handle being recycled: the device name, followed by the
+    /// EDID manufacturer, product and serial number when they are available (e.g.
+    /// `\\.\DISPLAY1/10AC40F44C383830`). Unlike the handle, it tells two monitors apart that
+    /// take turns on the same device name.
+    pub fn stable_id(&self) -> Option<String> {
+        let name = self.name()?;
+        match self.edid().as_deref().and_then(edid_product_id) {
+            Some(product_id) => Some(format!("{}/{}", name, product_id)),
+            None => Some(name),
+        }
+    }
+}
+
+
+/// Whether an output technology is a built-in panel rather than a connector.
+fn is_internal_output_technology(technology: DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY) -> bool {
+    matches!(
+        technology,
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL
+            | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED
+            | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED
+    )
+}
+
+fn output_technology_name(technology: DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY) -> &'static str {
+    match technology {
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_OTHER => "Other",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15 => "VGA",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SVIDEO => "S-Video",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_COMPOSITE_VIDEO => "Composite",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_COMPONENT_VIDEO => "Component",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI => "DVI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI => "HDMI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS => "LVDS",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_D_JPN => "D-Terminal",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SDI => "SDI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL => "DisplayPort",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED => "Embedded DisplayPort",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EXTERNAL => "UDI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED => "Embedded UDI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_SDTVDONGLE => "SDTV Dongle",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST => "Miracast",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_WIRED => "Indirect Wired",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INDIRECT_VIRTUAL => "Indirect Virtual",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_USB_TUNNEL => "DisplayPort over USB",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL => "Internal",
+        _ => "Unknown",
+    }
+}
+
+/// How the active displays are combined, as chosen with Win+P.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayTopology {
+    /// Every display shows its own part of the desktop.
+    Extend,
+    /// Every display shows the same image.
+    Clone,
+    /// Only a built-in panel is active.
+    InternalOnly,
+    /// Only a single external display is active.
+    ExternalOnly,
+    /// Some displays are duplicated and others extend the desktop.
+    Mixed,
+}
+
+impl DisplayTopology {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayTopology::Extend => "extend",
+            DisplayTopology::Clone => "clone",
+            DisplayTopology::InternalOnly => "internal",
+            DisplayTopology::ExternalOnly => "external",
+            DisplayTopology::Mixed => "mixed",
+        }
+    }
+
+    /// The `SDC_TOPOLOGY_*` flag that selects this topology, or `None` for `Mixed`, which can't be
+    /// requested directly.
+    fn sdc_flag(self) -> Option<u32> {
+        match self {
+            DisplayTopology::Extend => Some(SDC_TOPOLOGY_EXTEND),
+            DisplayTopology::Clone => Some(SDC_TOPOLOGY_CLONE),
+            DisplayTopology::InternalOnly => Some(SDC_TOPOLOGY_INTERNAL),
+            DisplayTopology::ExternalOnly => Some(SDC_TOPOLOGY_EXTERNAL),
+            DisplayTopology::Mixed => None,
+        }
+    }
+}
+
+impl FromStr for DisplayTopology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "extend" => Ok(DisplayTopology::Extend),
+            "clone" => Ok(DisplayTopology::Clone),
+            "internal" => Ok(DisplayTopology::InternalOnly),
+            "external" => Ok(DisplayTopology::ExternalOnly),
+            "mixed" => Ok(DisplayTopology::Mixed),
+            _ => Err(format!(
+                "invalid topology {:?}; expected 'extend', 'clone', 'internal' or 'external'",
+                s
+            )),
+        }
+    }
+}
+
+/// Works out the topology from the active paths: sources that feed several targets are cloned,
+/// and sources with a single target extend the desktop.
+pub fn display_topology() -> io::Result<DisplayTopology> {
+    let (paths, _) = query_display_config()?;
+    if let [path] = paths.as_slice() {
+        return Ok(if is_internal_output_technology(path.targetInfo.outputTechnology) {
+            DisplayTopology::InternalOnly
+        } else {
+            DisplayTopology::ExternalOnly
+        });
+    }
+    let mut targets_per_source: HashMap<(u32, i32, u32), usize> = HashMap::new();
+    for path in &paths {
+        let source = (path.sourceInfo.adapterId.LowPart, path.sourceInfo.adapterId.HighPart, path.sourceInfo.id);
+        *targets_per_source.entry(source).or_default() += 1;
+    }
+    let cloned_sources = targets_per_source.values().filter(|&&targets| targets > 1).count();
+    match (targets_per_source.len(), cloned_sources) {
+        (0, _) => Err(io::Error::other("no displays are active")),
+        (1, _) => Ok(DisplayTopology::Clone),
+        (_, 0) => Ok(DisplayTopology::Extend),
+        _ => Ok(DisplayTopology::Mixed),
+    }
+}
+
+/// Switches topology the way Win+P does, using the layout Windows last remembered for it.
+pub fn set_display_topology(topology: DisplayTopology) -> io::Result<()> {
+    let flag = topology
+        .sdc_flag()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "a mixed topology can't be set directly"))?;
+    let status = unsafe { SetDisplayConfig(0, null(), 0, null(), SDC_APPLY | flag) };
+    if status != ERROR_SUCCESS as i32 {
+        return Err(io::Error::from_raw_os_error(status));
+    }
+    Ok(())
+}
+
+/// The monitor that makes the most sense as the primary display: the built-in laptop panel if
+/// there is one, otherwise the largest monitor by area.
+pub fn suggest_primary_monitor() -> MonitorHandle {
+    monitors().find(MonitorHandle::is_internal).unwrap_or_else(largest_monitor)
+}
+
+/// The monitor with the greatest area, preferring the primary monitor on ties.
+pub fn largest_monitor() -> MonitorHandle {
+    monitors()
+        .max_by_key(|monitor| (monitor.area(), monitor.is_primary()))
+        .unwrap_or_else(primary_monitor)
+}
+
+/// The monitor whose current mode has the most pixels, preferring the primary monitor on ties.
+/// Unlike `largest_monitor`, this compares the real resolutions even when the process isn't DPI
+/// aware and `size` is scaled.
+pub fn highest_resolution_monitor() -> MonitorHandle {
+    monitors()
+        .max_by_key(|monitor| {
+            let pixels = monitor
+                .wide_name()
+                .and_then(|wide_name| get_dev_mode_wide(&wide_name).ok())
+                .map_or(0, |devmode| devmode.dmPelsWidth as u64 * devmode.dmPelsHeight as u64);
+            (pixels, monitor.is_primary())
+        })
+        .unwrap_or_else(primary_monitor)
+}
+
+
+// Pixel format
+
+/// Channel order of 32-bit pixels read back from GDI (e.g. `GetDIBits` with `BI_RGB`): one byte
+/// each of blue, green, red and alpha/padding, in that order in memory.
+pub const GDI_CHANNEL_ORDER: &str = "BGRA";
+
+/// The layout of a pixel in the desktop framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// 32 bits per pixel: blue, green, red, then an unused or alpha byte.
+    Bgra8,
+    /// 24 bits per pixel: blue, green, red.
+    Bgr8,
+    /// 16 bits per pixel, packed little-endian as 5 bits red, 6 green, 5 blue.
+    Bgr565,
+    /// 8 or fewer bits per pixel, as indexes into a palette.
+    Indexed(u32),
+}
+
+impl PixelFormat {
+    pub fn from_bits_per_pixel(bits_per_pixel: u32) -> Option<Self> {
+        match bits_per_pixel {
+            32 => Some(PixelFormat::Bgra8),
+            24 => Some(PixelFormat::Bgr8),
+            16 => Some(PixelFormat::Bgr565),
+            1..=8 => Some(PixelFormat::Indexed(bits_per_pixel)),
+            _ => None,
+        }
+    }
+
+    pub fn bits_per_pixel(self) -> u32 {
+        match self {
+            PixelFormat::Bgra8 => 32,
+            PixelFormat::Bgr8 => 24,
+            PixelFormat::Bgr565 => 16,
+            PixelFormat::Indexed(bits_per_pixel) => bits_per_pixel,
+        }
+    }
+
+    /// The channels of a pixel in memory order, or `"indexed"` for palette formats.
+    pub fn channel_order(self) -> &'static str {
+        match self {
+            PixelFormat::Bgra8 => GDI_CHANNEL_ORDER,
+            PixelFormat::Bgr8 | PixelFormat::Bgr565 => "BGR",
+            PixelFormat::Indexed(_) => "indexed",
+        }
+    }
+}
+
+/// The pixel format of the primary monitor's current mode.
+pub fn desktop_pixel_format() -> Option<PixelFormat> {
+    let wide_name = primary_monitor().wide_name()?;
+    let devmode = get_dev_mode_wide(&wide_name).ok()?;
+    PixelFormat::from_bits_per_pixel(devmode.dmBitsPerPel)
+}
+
+
+// Display change notifications
+
+type MessageHandler = Box<dyn FnMut(u32, WPARAM, LPARAM) + Send>;
+
+thread_local! {
+    // Each watcher window runs on its own thread, so the handler can live in thread-local storage
+    // instead of being smuggled through the window's user data.
+    static MESSAGE_HANDLER: RefCell<Option<MessageHandler>> = RefCell::new(None);
+}
+
+static MESSAGE_WINDOW_CLASS: Lazy<Vec<u16>> = Lazy::new(|| {
+    let class_name = wide_string("wmutil_display_watcher");
+    let class = WNDCLASSEXW {
+        cbSize: size_of::<WNDCLASSEXW>() as u32,
+        style: 0,
+        lpfnWndProc: Some(message_window_proc),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: unsafe { GetModuleHandleW(null()) },
+        hIcon: 0,
+        hCursor: 0,
+        hbrBackground: 0,
+        lpszMenuName: null(),
+        lpszClassName: class_name.as_ptr(),
+        hIconSm: 0,
+    };
+    unsafe { RegisterClassExW(&class) };
+    class_name
+});
+
+unsafe extern "system" fn message_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    MESSAGE_HANDLER.with(|handler| {
+        if let Some(handler) = handler.borrow_mut().as_mut() {
+            handler(msg, wparam, lparam);
+        }
+    });
+    match msg {
+        WM_CLOSE => {
+            unsafe { DestroyWindow(hwnd) };
+            0
+        }
+        WM_DESTROY => {
+            unsafe { PostQuitMessage(0) };
+            0
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
+
+/// A hidden window pumping messages on a background thread until it is stopped.
+///
+/// Broadcasts such as `WM_DISPLAYCHANGE` are only delivered to top-level windows, so this is an
+/// ordinary window that is never shown rather than a message-only (`HWND_MESSAGE`) window.
+pub struct DisplayWatcher {
+    hwnd: HWND,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DisplayWatcher {
+    /// Spawns the window thread. `make_handler` runs on that thread once it is per-monitor DPI
+    /// aware, so any state it captures reflects the real (unvirtualized) DPI values.
+    pub(crate) fn spawn<F>(make_handler: F) -> io::Result<Self>
+    where
+        F: FnOnce() -> MessageHandler + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            if let Some(set_thread_dpi_awareness_context) = *SET_THREAD_DPI_AWARENESS_CONTEXT {
+                unsafe { set_thread_dpi_awareness_context(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+            }
+            let hwnd = unsafe {
+                CreateWindowExW(
+                    0,
+                    MESSAGE_WINDOW_CLASS.as_ptr(),
+                    null(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    GetModuleHandleW(null()),
+                    null(),
+                )
+            };
+            if hwnd == 0 {
+                let _ = ready_tx.send(Err(io::Error::last_os_error()));
+                return;
+            }
+            MESSAGE_HANDLER.with(|handler| *handler.borrow_mut() = Some(make_handler()));
+            let _ = ready_tx.send(Ok(hwnd));
+
+            let mut msg: MSG = unsafe { mem::zeroed() };
+            while unsafe { GetMessageW(&mut msg, 0, 0, 0) } > 0 {
+                unsafe {
+                    TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+            MESSAGE_HANDLER.with(|handler| handler.borrow_mut().take());
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(hwnd)) => Ok(DisplayWatcher { hwnd, thread: Some(thread) }),
+            Ok(Err(err)) => {
+                let _ = thread.join();
+                Err(err)
+            }
+            Err(_) => Err(io::Error::other("display watcher thread exited unexpectedly")),
+        }
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// Closes the hidden window and waits for its thread to finish.
+    pub fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            unsafe { PostMessageW(self.hwnd, WM_CLOSE, 0, 0) };
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for DisplayWatcher {
+    fn drop(&mut self) {
+        // Don't join here: the handler may be waiting on a lock (e.g. the GIL) held by whoever is
+        // dropping us. The thread exits on its own once the window is closed.
+        if self.thread.take().is_some() {
+            unsafe { PostMessageW(self.hwnd, WM_CLOSE, 0, 0) };
+        }
+    }
+}
+
+fn monitor_dpis() -> HashMap<HMONITOR, u32> {
+    available_monitors()
+        .into_iter()
+        .filter_map(|monitor| get_monitor_dpi(monitor.0).map(|dpi| (monitor.0, dpi)))
+        .collect()
+}
+
+/// Calls `callback` with the monitor and its new effective DPI whenever a monitor's DPI changes.
+///
+/// `WM_DPICHANGED` is only sent for the monitor the hidden window sits on, so the effective DPI of
+/// every monitor is also re-polled on `WM_DISPLAYCHANGE` and `WM_SETTINGCHANGE`. Monitors that
+/// appear after the watcher starts are tracked from then on but not reported.
+pub fn watch_dpi_changes<F>(callback: F) -> io::Result<DisplayWatcher>
+where
+    F: FnMut(MonitorHandle, u32) + Send + 'static,
+{
+    DisplayWatcher::spawn(move || {
+        let mut on_message = dpi_change_handler(callback);
+        Box::new(move |msg, _wparam, _lparam| on_message(msg))
+    })
+}
+
+/// The message handling behind `watch_dpi_changes`. Must be created on the watcher thread so the
+/// initial DPI values aren't virtualized.
+fn dpi_change_handler<F>(mut callback: F) -> impl FnMut(u32)
+where
+    F: FnMut(MonitorHandle, u32),
+{
+    let mut known = monitor_dpis();
+    move |msg| {
+        if !matches!(msg, WM_DPICHANGED | WM_DISPLAYCHANGE | WM_SETTINGCHANGE) {
+            return;
+        }
+        for (hmonitor, dpi) in monitor_dpis() {
+            if let Some(previous) = known.insert(hmonitor, dpi) {
+                if previous != dpi {
+                    callback(MonitorHandle::new(hmonitor), dpi);
+                }
+            }
+        }
+    }
+}
+
+/// Calls `callback` with the freshly enumerated monitors whenever the display configuration
+/// changes: a monitor is attached or detached, or a resolution, orientation or position changes.
+///
+/// Windows often sends several `WM_DISPLAYCHANGE` messages for one change, so the callback may run
+/// more than once with the same monitors.
+pub fn watch_display_changes<F>(mut callback: F) -> io::Result<DisplayWatcher>
+where
+    F: FnMut(Vec<MonitorHandle>) + Send + 'static,
+{
+    DisplayWatcher::spawn(move || {
+        Box::new(move |msg, _wparam, _lparam| {
+            if msg == WM_DISPLAYCHANGE {
+                callback(monitors().collect());
+            }
+        })
+    })
+}
+
+/// `watch_display_changes` and `watch_dpi_changes` on a single hidden window. Changing a monitor's
+/// scaling doesn't always send `WM_DISPLAYCHANGE`, so `dpi_callback` is how those changes surface.
+pub fn watch_display_and_dpi_changes<F, G>(mut callback: F, dpi_callback: G) -> io::Result<DisplayWatcher>
+where
+    F: FnMut(Vec<MonitorHandle>) + Send + 'static,
+    G: FnMut(MonitorHandle, u32) + Send + 'static,
+{
+    DisplayWatcher::spawn(move || {
+        let mut on_dpi_message = dpi_change_handler(dpi_callback);
+        Box::new(move |msg, _wparam, _lparam| {
+            if msg == WM_DISPLAYCHANGE {
+                callback(monitors().collect());
+            }
+            on_dpi_message(msg);
+        })
+    })
+}
+
+/// Blocks until the display configuration changes or `timeout` elapses, returning whether a
+/// change happened. With no timeout this waits indefinitely.
+pub fn wait_for_display_change(timeout: Option<Duration>) -> io::Result<bool> {
+    let (changed_tx, changed_rx) = mpsc::channel();
+    let mut watcher = DisplayWatcher::spawn(move || {
+        Box::new(move |msg, _wparam, _lparam| {
+            if msg == WM_DISPLAYCHANGE {
+                let _ = changed_tx.send(());
+            }
+        })
+    })?;
+    let changed = match timeout {
+        Some(timeout) => changed_rx.recv_timeout(timeout).is_ok(),
+        None => changed_rx.recv().is_ok(),
+    };
+    watcher.stop();
+    Ok(changed)
+}
+
+
+static DISPLAY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static GENERATION_WATCHER: Lazy<Option<DisplayWatcher>> = Lazy::new(|| {
+    DisplayWatcher::spawn(|| {
+        Box::new(|msg, _wparam, _lparam| {
+            if msg == WM_DISPLAYCHANGE {
+                DISPLAY_GENERATION.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    })
+    .ok()
+});
+
+/// A counter that goes up every time the display configuration changes. Comparing it against a
+/// value saved alongside a `MonitorHandle` is a cheap way to tell that the handle may be stale.
+///
+/// Counting starts on the first call, which starts a watcher thread for the rest of the process.
+pub fn display_generation() -> u64 {
+    let _ = &*GENERATION_WATCHER;
+    DISPLAY_GENERATION.load(Ordering::SeqCst)
+}
+
+
+// Display configuration
+
+/// Builds a `DEVMODEW` for display mode changes.
+///
+/// Each setter records the matching `DM_*` bit in `dmFields`, so only the fields that were
+/// actually set are considered by `ChangeDisplaySettingsExW`.
+#[derive(Clone, Copy)]
+pub struct DevModeBuilder {
+    devmode: DEVMODEW,
+}
+
+impl DevModeBuilder {
+    /// Starts from an empty `DEVMODEW` with only `dmSize` filled in.
+    pub fn new() -> Self {
+        let mut devmode: DEVMODEW = unsafe { mem::zeroed() };
+        devmode.dmSize = size_of::<DEVMODEW>() as u16;
+        DevModeBuilder { devmode }
+    }
+
+    /// Starts from an existing `DEVMODEW`, such as one returned by `EnumDisplaySettingsW`.
+    ///
+    /// `dmDriverExtra` is cleared because the private driver data is not carried along.
+    pub fn from_devmode(mut devmode: DEVMODEW) -> Self {
+        devmode.dmSize = size_of::<DEVMODEW>() as u16;
+        devmode.dmDriverExtra = 0;
+        DevModeBuilder { devmode }
+    }
+
+    pub fn width(mut self, width: u32) -> Self {
+        self.devmode.dmPelsWidth = width;
+        self.devmode.dmFields |= DM_PELSWIDTH;
+        self
+    }
+
+    pub fn height(mut self, height: u32) -> Self {
+        self.devmode.dmPelsHeight = height;
+        self.devmode.dmFields |= DM_PELSHEIGHT;
+        self
+    }
+
+    pub fn refresh_rate(mut self, hz: u32) -> Self {
+        self.devmode.dmDisplayFrequency = hz;
+        self.devmode.dmFields |= DM_DISPLAYFREQUENCY;
+        self
+    }
+
+    pub fn bits_per_pixel(mut self, bits_per_pixel: u32) -> Self {
+        self.devmode.dmBitsPerPel = bits_per_pixel;
+        self.devmode.dmFields |= DM_BITSPERPEL;
+        self
+    }
+
+    /// Sets the display orientation to one of the `DMDO_*` values.
+    pub fn orientation(mut self, orientation: DEVMODE_DISPLAY_ORIENTATION) -> Self {
+        self.devmode.Anonymous1.Anonymous2.dmDisplayOrientation = orientation;
+        self.devmode.dmFields |= DM_DISPLAYORIENTATION;
+        self
+    }
+
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.devmode.Anonymous1.Anonymous2.dmPosition = POINTL { x, y };
+        self.devmode.dmFields |= DM_POSITION;
+        self
+    }
+
+    pub fn build(self) -> DEVMODEW {
+        self.devmode
+    }
+}
+
+impl Default for DevModeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn get_dev_mode_wide(wide_name: &[u16]) -> Result<DEVMODEW, String> {
+    let mut devmode = DevModeBuilder::new().build();
+
+    let success = unsafe {
+        EnumDisplaySettingsW(wide_name.as_ptr(), ENUM_CURRENT_SETTINGS, &mut devmode)
+    };
+
+    if success == 0 {
+        return Err(format!("Failed to retrieve settings for display: {}", decode_wide(wide_name).to_string_lossy()));
+    }
+
+    Ok(devmode)
+}
+
+/// The outcome of validating a settings change with `CDS_TEST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayChangeStatus {
+    /// The change would be applied.
+    Successful,
+    /// The change would only take effect after a restart.
+    Restart,
+    /// The monitor doesn't support the requested mode.
+    BadMode,
+    /// Any other `DISP_CHANGE_*` failure.
+    Failed(DISP_CHANGE),
+}
+
+impl DisplayChangeStatus {
+    fn from_code(code: DISP_CHANGE) -> Self {
+        match code {
+            DISP_CHANGE_SUCCESSFUL => DisplayChangeStatus::Successful,
+            DISP_CHANGE_RESTART => DisplayChangeStatus::Restart,
+            DISP_CHANGE_BADMODE => DisplayChangeStatus::BadMode,
+            code => DisplayChangeStatus::Failed(code),
+        }
+    }
+
+    fn code(self) -> DISP_CHANGE {
+        match self {
+            DisplayChangeStatus::Successful => DISP_CHANGE_SUCCESSFUL,
+            DisplayChangeStatus::Restart => DISP_CHANGE_RESTART,
+            DisplayChangeStatus::BadMode => DISP_CHANGE_BADMODE,
+            DisplayChangeStatus::Failed(code) => code,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayChangeStatus::Successful => "successful",
+            DisplayChangeStatus::Restart => "restart",
+            DisplayChangeStatus::BadMode => "bad_mode",
+            DisplayChangeStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+/// Why `ChangeDisplaySettingsExW` refused a change, from its `DISP_CHANGE_*` result.
+///
+/// Functions that change display settings return these wrapped in an `io::Error`; use
+/// `DisplayChangeError::from_io_error` to get them back out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayChangeError {
+    BadMode,
+    BadFlags,
+    /// The change only takes effect after a restart.
+    Restart,
+    Failed,
+    NotUpdated,
+    BadParam,
+    BadDualView,
+    /// A code this crate doesn't know about.
+    Other(DISP_CHANGE),
+}
+
+impl DisplayChangeError {
+    /// Returns `None` for `DISP_CHANGE_SUCCESSFUL`.
+    pub fn from_code(code: DISP_CHANGE) -> Option<Self> {
+        match code {
+            DISP_CHANGE_SUCCESSFUL => None,
+            DISP_CHANGE_BADMODE => Some(DisplayChangeError::BadMode),
+            DISP_CHANGE_BADFLAGS => Some(DisplayChangeError::BadFlags),
+            DISP_CHANGE_RESTART => Some(DisplayChangeError::Restart),
+            DISP_CHANGE_FAILED => Some(DisplayChangeError::Failed),
+            DISP_CHANGE_NOTUPDATED => Some(DisplayChangeError::NotUpdated),
+            DISP_CHANGE_BADPARAM => Some(DisplayChangeError::BadParam),
+            DISP_CHANGE_BADDUALVIEW => Some(DisplayChangeError::BadDualView),
+            code => Some(DisplayChangeError::Other(code)),
+        }
+    }
+
+    pub fn code(self) -> DISP_CHANGE {
+        match self {
+            DisplayChangeError::BadMode => DISP_CHANGE_BADMODE,
+            DisplayChangeError::BadFlags => DISP_CHANGE_BADFLAGS,
+            DisplayChangeError::Restart => DISP_CHANGE_RESTART,
+            DisplayChangeError::Failed => DISP_CHANGE_FAILED,
+            DisplayChangeError::NotUpdated => DISP_CHANGE_NOTUPDATED,
+            DisplayChangeError::BadParam => DISP_CHANGE_BADPARAM,
+            DisplayChangeError::BadDualView => DISP_CHANGE_BADDUALVIEW,
+            DisplayChangeError::Other(code) => code,
+        }
+    }
+
+    /// The name of the `DISP_CHANGE_*` constant.
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayChangeError::BadMode => "DISP_CHANGE_BADMODE",
+            DisplayChangeError::BadFlags => "DISP_CHANGE_BADFLAGS",
+            DisplayChangeError::Restart => "DISP_CHANGE_RESTART",
+            DisplayChangeError::Failed => "DISP_CHANGE_FAILED",
+            DisplayChangeError::NotUpdated => "DISP_CHANGE_NOTUPDATED",
+            DisplayChangeError::BadParam => "DISP_CHANGE_BADPARAM",
+            DisplayChangeError::BadDualView => "DISP_CHANGE_BADDUALVIEW",
+            DisplayChangeError::Other(_) => "unknown",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            DisplayChangeError::BadMode => "the graphics mode is not supported",
+            DisplayChangeError::BadFlags => "an invalid set of flags was passed in",
+            DisplayChangeError::Restart => "the computer must be restarted for the graphics mode to work",
+            DisplayChangeError::Failed => "the display driver failed the specified graphics mode",
+            DisplayChangeError::NotUpdated => "unable to write settings to the registry",
+            DisplayChangeError::BadParam => "an invalid parameter was passed in",
+            DisplayChangeError::BadDualView => {
+                "the settings change was unsuccessful because the system is DualView capable"
+            }
+            DisplayChangeError::Other(_) => "unknown ChangeDisplaySettingsExW result",
+        }
+    }
+
+    /// The `DisplayChangeError` inside `err`, if it came from a display settings change.
+    pub fn from_io_error(err: &io::Error) -> Option<Self> {
+        let inner = err.get_ref()?;
+        inner
+            .downcast_ref::<DisplayChangeError>()
+            .or_else(|| inner.downcast_ref::<DeviceDisplayChangeError>().map(|err| &err.error))
+            .copied()
+    }
+}
+
+impl std::fmt::Display for DisplayChangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}, code {})", self.message(), self.name(), self.code())
+    }
+}
+
+impl std::error::Error for DisplayChangeError {}
+
+impl From<DisplayChangeError> for io::Error {
+    fn from(err: DisplayChangeError) -> Self {
+        io::Error::other(err)
+    }
+}
+
+/// A `DisplayChangeError` for a particular device, so the message says which one failed.
+#[derive(Debug)]
+struct DeviceDisplayChangeError {
+    device_name: String,
+    error: DisplayChangeError,
+}
+
+impl std::fmt::Display for DeviceDisplayChangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.device_name, self.error)
+    }
+}
+
+impl std::error::Error for DeviceDisplayChangeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+fn disp_change_result(code: DISP_CHANGE) -> io::Result<()> {
+    match DisplayChangeError::from_code(code) {
+        None => Ok(()),
+        Some(err) => Err(err.into()),
+    }
+}
+
+/// Queues a settings change for `display_name`; nothing takes effect until
+/// `commit_display_settings` is called.
+fn stage_display_settings(display_name: impl Into<DeviceName>, devmode: &DEVMODEW, flags: CDS_TYPE) -> io::Result<()> {
+    stage_display_settings_wide(display_name.into().as_wide(), devmode, flags)
+}
+
+/// Like `stage_display_settings`, but takes the NUL-terminated device name as-is.
+fn stage_display_settings_wide(wide_name: &[u16], devmode: &DEVMODEW, flags: CDS_TYPE) -> io::Result<()> {
+    change_display_settings_wide(wide_name, devmode, flags | CDS_UPDATEREGISTRY | CDS_NORESET)
+}
+
+fn change_display_settings_wide(wide_name: &[u16], devmode: &DEVMODEW, flags: CDS_TYPE) -> io::Result<()> {
+    let result = unsafe { ChangeDisplaySettingsExW(wide_name.as_ptr(), devmode, 0, flags, null()) };
+    match DisplayChangeError::from_code(result) {
+        None => Ok(()),
+        Some(error) => Err(io::Error::other(DeviceDisplayChangeError {
+            device_name: decode_wide(wide_name).to_string_lossy().into_owned(),
+            error,
+        })),
+    }
+}
+
+/// The flags for an immediate change: saved to the registry when `persist` is true, otherwise
+/// dynamic only and lost at the next reboot or sign-out.
+fn persist_flags(persist: bool) -> CDS_TYPE {
+    if persist {
+        CDS_UPDATEREGISTRY
+    } else {
+        0
+    }
+}
+
+fn test_display_settings_wide(wide_name: &[u16], devmode: &DEVMODEW) -> DisplayChangeStatus {
+    let result = unsafe { ChangeDisplaySettingsExW(wide_name.as_ptr(), devmode, 0, CDS_TEST, null()) };
+    DisplayChangeStatus::from_code(result)
+}
+
+/// Applies every change queued by `stage_display_settings` at once.
+fn commit_display_settings() -> io::Result<()> {
+    disp_change_result(commit_display_settings_code())
+}
+
+/// Like `commit_display_settings`, but returns the status instead of failing on it.
+fn commit_display_settings_status() -> DisplayChangeStatus {
+    DisplayChangeStatus::from_code(commit_display_settings_code())
+}
+
+fn commit_display_settings_code() -> DISP_CHANGE {
+    unsafe { ChangeDisplaySettingsExW(null(), null(), 0, 0, null()) }
+}
+
+/// Stages `primary` at the desktop origin and shifts every other monitor by the same offset so the
+/// layout is preserved. Monitors in `skip` are left alone.
+///
+/// Every monitor's settings are read before anything is staged. With `strict`, a monitor that
+/// disappears mid-operation fails the whole change up front; otherwise it is left where it is and
+/// reported in `PrimaryChangeReport::skipped`.
+fn stage_primary_monitor(
+    primary: &MonitorHandle,
+    skip: &[&MonitorHandle],
+    persist: bool,
+    strict: bool,
+) -> io::Result<PrimaryChangeReport> {
+    let (layout, skipped) = primary_monitor_layout(primary, skip, strict)?;
+    let repositioned = layout.iter().map(|(snapshot, _, _)| snapshot.monitor.clone()).collect();
+    let changes: Vec<_> = layout
+        .into_iter()
+        .map(|(snapshot, position, flags)| snapshot.into_change(flags, position))
+        .collect();
+    stage_changes(&changes, persist)?;
+    Ok(PrimaryChangeReport { repositioned, skipped })
+}
+
+/// Which monitors a primary monitor change moved and which it had to leave alone.
+#[derive(Debug, Default)]
+pub struct PrimaryChangeReport {
+    /// The monitors that were staged, the new primary included.
+    pub repositioned: Vec<MonitorHandle>,
+    /// The monitors whose settings couldn't be read, such as ones detached mid-operation, and why.
+    pub skipped: Vec<(MonitorHandle, io::Error)>,
+}
+
+/// Makes `primary` the primary monitor, shifting the others so the layout is preserved. See
+/// `stage_primary_monitor` for what `strict` does.
+pub fn set_primary_monitor(primary: &MonitorHandle, persist: bool, strict: bool) -> io::Result<PrimaryChangeReport> {
+    if primary.is_primary() {
+        return Ok(PrimaryChangeReport::default());
+    }
+    let report = stage_primary_monitor(primary, &[], persist, strict)?;
+    if persist {
+        // Without persist each change was already applied dynamically; committing would reload
+        // the registry.
+        commit_display_settings()?;
+    }
+    Ok(report)
+}
+
+/// A settings change read and built ahead of staging, along with the settings it replaces.
+struct PendingChange {
+    wide_name: Vec<u16>,
+    original: DEVMODEW,
+    devmode: DEVMODEW,
+    flags: CDS_TYPE,
+}
+
+impl PendingChange {
+    fn prepare(
+        monitor: &MonitorHandle,
+        flags: CDS_TYPE,
+        edit: impl FnOnce(DevModeBuilder) -> DevModeBuilder,
+    ) -> io::Result<Self> {
+        let wide_name = monitor
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let original = get_dev_mode_wide(&wide_name).map_err(io::Error::other)?;
+        Ok(Self::from_dev_mode(wide_name, original, flags, edit))
+    }
+
+    /// Like `prepare`, but starting from settings that have already been read.
+    fn from_dev_mode(
+        wide_name: Vec<u16>,
+        original: DEVMODEW,
+        flags: CDS_TYPE,
+        edit: impl FnOnce(DevModeBuilder) -> DevModeBuilder,
+    ) -> Self {
+        let devmode = edit(DevModeBuilder::from_devmode(original)).build();
+        PendingChange { wide_name, original, devmode, flags }
+    }
+}
+
+/// A monitor's device name and current settings, read once so operations that rearrange every
+/// monitor don't query each one over and over.
+struct MonitorSnapshot {
+    monitor: MonitorHandle,
+    wide_name: Vec<u16>,
+    devmode: DEVMODEW,
+}
+
+impl MonitorSnapshot {
+    fn capture(monitor: MonitorHandle) -> io::Result<Self> {
+        let wide_name = wide_device_name(&get_monitor_info(monitor.0)?);
+        let devmode = get_dev_mode_wide(&wide_name).map_err(io::Error::other)?;
+        Ok(MonitorSnapshot { monitor, wide_name, devmode })
+    }
+
+    /// The position from `dmPosition`. Unlike `rcMonitor`, which a process that isn't per-monitor
+    /// DPI aware sees scaled on monitors whose DPI differs from the system DPI, this is always in
+    /// physical pixels, the same space positions are written back in.
+    fn position(&self) -> PhysicalPosition<i32> {
+        let position = unsafe { self.devmode.Anonymous1.Anonymous2.dmPosition };
+        PhysicalPosition { x: position.x, y: position.y }
+    }
+
+    fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize { width: self.devmode.dmPelsWidth, height: self.devmode.dmPelsHeight }
+    }
+
+    fn into_change(self, flags: CDS_TYPE, position: PhysicalPosition<i32>) -> PendingChange {
+        PendingChange::from_dev_mode(self.wide_name, self.devmode, flags, |devmode| {
+            devmode.position(position.x, position.y)
+        })
+    }
+}
+
+/// Stages every change in order. If one fails, the ones already staged are re-staged with their
+/// original settings so that a later commit doesn't apply half of the change.
+///
+/// With `persist` false the changes can't be staged, because `CDS_NORESET` only works together
+/// with `CDS_UPDATEREGISTRY`; each one is instead applied immediately as a dynamic change, and the
+/// caller must not call `commit_display_settings`, which would reload the registry settings.
+fn stage_changes(changes: &[PendingChange], persist: bool) -> io::Result<()> {
+    let apply = |wide_name: &[u16], devmode: &DEVMODEW, flags: CDS_TYPE| {
+        if persist {
+            stage_display_settings_wide(wide_name, devmode, flags)
+        } else {
+            change_display_settings_wide(wide_name, devmode, flags)
+        }
+    };
+    for (index, change) in changes.iter().enumerate() {
+        if let Err(err) = apply(&change.wide_name, &change.devmode, change.flags) {
+            for staged in &changes[..index] {
+                if let Err(restore_err) = apply(&staged.wide_name, &staged.original, 0) {
+                    tracing::warn!("failed to restore staged display settings: {}", restore_err);
+                }
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// A monitor's settings, the position it moves to and the flags to stage it with.
+type LayoutMove = (MonitorSnapshot, PhysicalPosition<i32>, CDS_TYPE);
+/// A monitor left out of a layout change, with the error that prevented reading it.
+type SkippedMonitor = (MonitorHandle, io::Error);
+
+/// Where each monitor ends up, in physical pixels, when the one marked primary moves to the
+/// desktop origin and the others keep their place relative to it. Takes each monitor's
+/// `dmPosition`, size and whether it is the new primary, and returns the rectangles in the same
+/// order; `None` if no monitor is marked primary.
+fn primary_layout_rects(monitors: &[(PhysicalPosition<i32>, PhysicalSize<u32>, bool)]) -> Option<Vec<RECT>> {
+    let (origin, _, _) = monitors.iter().find(|(_, _, is_primary)| *is_primary)?;
+    Some(
+        monitors
+            .iter()
+            .map(|(position, size, _)| {
+                let left = position.x - origin.x;
+                let top = position.y - origin.y;
+                RECT { left, top, right: left + size.width as i32, bottom: top + size.height as i32 }
+            })
+            .collect(),
+    )
+}
+
+/// The new position and flags of every monitor when `primary` becomes the primary monitor, with
+/// `primary` itself last so its `CDS_SET_PRIMARY` change wins.
+///
+/// Monitors whose settings can't be read fail the layout when `strict` is set, and are returned
+/// separately otherwise. The new primary itself must always be readable.
+fn primary_monitor_layout(
+    primary: &MonitorHandle,
+    skip: &[&MonitorHandle],
+    strict: bool,
+) -> io::Result<(Vec<LayoutMove>, Vec<SkippedMonitor>)> {
+    let mut snapshots = Vec::new();
+    let mut unreadable = Vec::new();
+    for monitor in available_monitors().into_iter().filter(|monitor| !skip.contains(&monitor)) {
+        match MonitorSnapshot::capture(monitor.clone()) {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(err) if strict || monitor == *primary => return Err(err),
+            Err(err) => unreadable.push((monitor, err)),
+        }
+    }
+    let geometry: Vec<_> = snapshots
+        .iter()
+        .map(|snapshot| (snapshot.position(), snapshot.size(), snapshot.monitor == *primary))
+        .collect();
+    let rects = primary_layout_rects(&geometry)
+        .ok_or_else(|| io::Error::other("the new primary monitor is no longer attached"))?;
+    let (new_primary, mut layout): (Vec<_>, Vec<_>) = snapshots
+        .into_iter()
+        .zip(rects)
+        .map(|(snapshot, rect)| {
+            let flags = if snapshot.monitor == *primary { CDS_SET_PRIMARY } else { 0 };
+            (snapshot, PhysicalPosition { x: rect.left, y: rect.top }, flags)
+        })
+        .partition(|(_, _, flags)| *flags == CDS_SET_PRIMARY);
+    layout.extend(new_primary);
+    Ok((layout, unreadable))
+}
+
+/// Checks with `CDS_TEST`, without changing anything, whether making `primary` the primary
+/// monitor would work. Returns the first status that isn't `Successful`.
+pub fn test_primary_monitor(primary: &MonitorHandle) -> io::Result<DisplayChangeStatus> {
+    let (layout, _) = primary_monitor_layout(primary, &[], true)?;
+    for (snapshot, position, _) in layout {
+        let change = snapshot.into_change(0, position);
+        let status = test_display_settings_wide(&change.wide_name, &change.devmode);
+        if status != DisplayChangeStatus::Successful {
+            return Ok(status);
+        }
+    }
+    Ok(DisplayChangeStatus::Successful)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionOp {
+    Mode { width: u32, height: u32 },
+    RefreshRate(u32),
+    Position(i32, i32),
+}
+
+/// Display changes collected in memory and then staged and applied in a single commit, so the
+/// screen only flickers once. Nothing is touched until `commit`; dropping the transaction
+/// discards it.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayTransaction {
+    ops: Vec<(MonitorHandle, TransactionOp)>,
+    primary: Option<MonitorHandle>,
+}
+
+impl DisplayTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a resolution change, failing with `InvalidInput` right away if the monitor doesn't
+    /// support it or `set_primary` was already queued.
+    pub fn set_mode(&mut self, monitor: &MonitorHandle, width: u32, height: u32) -> io::Result<()> {
+        self.check_no_primary("set_mode")?;
+        if !monitor.supports_mode(width, height, None) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: {}x{} is not a supported mode", monitor.name().unwrap_or_default(), width, height),
+            ));
+        }
+        self.ops.push((monitor.clone(), TransactionOp::Mode { width, height }));
+        Ok(())
+    }
+
+    pub fn set_refresh_rate(&mut self, monitor: &MonitorHandle, hz: u32) {
+        self.ops.push((monitor.clone(), TransactionOp::RefreshRate(hz)));
+    }
+
+    /// Queues a move, failing with `InvalidInput` if `set_primary` was already queued.
+    pub fn set_position(&mut self, monitor: &MonitorHandle, x: i32, y: i32) -> io::Result<()> {
+        self.check_no_primary("set_position")?;
+        self.ops.push((monitor.clone(), TransactionOp::Position(x, y)));
+        Ok(())
+    }
+
+    /// Makes `monitor` the primary monitor, shifting the others like `set_primary_monitor` does.
+    /// The shift is worked out from the current layout, so this fails with `InvalidInput` if a
+    /// `set_mode` or `set_position` is already queued, since either would change that layout.
+    pub fn set_primary(&mut self, monitor: &MonitorHandle) -> io::Result<()> {
+        let changes_layout = self
+            .ops
+            .iter()
+            .any(|(_, op)| matches!(op, TransactionOp::Mode { .. } | TransactionOp::Position(..)));
+        if changes_layout {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "set_primary can't be combined with set_mode or set_position in one transaction",
+            ));
+        }
+        self.primary = Some(monitor.clone());
+        Ok(())
+    }
+
+    fn check_no_primary(&self, operation: &str) -> io::Result<()> {
+        if self.primary.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} can't be combined with set_primary in one transaction", operation),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty() && self.primary.is_none()
+    }
+
+    /// Stages every queued change and applies them together, returning the commit's status.
+    /// If staging fails, nothing is applied.
+    pub fn commit(self) -> io::Result<DisplayChangeStatus> {
+        // Group the operations by monitor, keeping the order they were queued in.
+        let mut monitors: Vec<(MonitorHandle, Vec<TransactionOp>, CDS_TYPE)> = Vec::new();
+        fn entry<'a>(
+            monitors: &'a mut Vec<(MonitorHandle, Vec<TransactionOp>, CDS_TYPE)>,
+            monitor: &MonitorHandle,
+        ) -> &'a mut (MonitorHandle, Vec<TransactionOp>, CDS_TYPE) {
+            let index = match monitors.iter().position(|(existing, _, _)| existing == monitor) {
+                Some(index) => index,
+                None => {
+                    monitors.push((monitor.clone(), Vec::new(), 0));
+                    monitors.len() - 1
+                }
+            };
+            &mut monitors[index]
+        }
+        for (monitor, op) in &self.ops {
+            entry(&mut monitors, monitor).1.push(*op);
+        }
+        if let Some(primary) = &self.primary {
+            let (layout, _) = primary_monitor_layout(primary, &[], true)?;
+            for (snapshot, position, flags) in layout {
+                let (_, ops, monitor_flags) = entry(&mut monitors, &snapshot.monitor);
+                ops.push(TransactionOp::Position(position.x, position.y));
+                *monitor_flags |= flags;
+            }
+            // Like set_primary_monitor, stage the primary last so its CDS_SET_PRIMARY change wins.
+            monitors.sort_by_key(|(monitor, _, _)| monitor == primary);
+        }
+
+        let changes = monitors
+            .iter()
+            .map(|(monitor, ops, flags)| {
+                PendingChange::prepare(monitor, *flags, |mut devmode| {
+                    for op in ops {
+                        devmode = match *op {
+                            TransactionOp::Mode { width, height } => devmode.width(width).height(height),
+                            TransactionOp::RefreshRate(hz) => devmode.refresh_rate(hz),
+                            TransactionOp::Position(x, y) => devmode.position(x, y),
+                        };
+                    }
+                    devmode
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        stage_changes(&changes, true)?;
+        Ok(commit_display_settings_status())
+    }
+}
+
+/// The zero-sized mode that detaches a display from the desktop.
+fn detached_dev_mode() -> DEVMODEW {
+    DevModeBuilder::new().width(0).height(0).position(0, 0).build()
+}
+
+/// Stages detaching `monitor` from the desktop.
+fn stage_disable_monitor(monitor: &MonitorHandle) -> io::Result<()> {
+    let wide_name = monitor
+        .wide_name()
+        .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+    stage_display_settings_wide(&wide_name, &detached_dev_mode(), 0)
+}
+
+/// Detaches `monitor` from the desktop. If it is the primary monitor, another active monitor is
+/// promoted first, in the same commit, so the desktop is never left without a primary.
+pub fn disable_monitor_safely(monitor: &MonitorHandle) -> io::Result<()> {
+    let replacement = available_monitors().into_iter().find(|other| other != monitor);
+    let Some(replacement) = replacement else {
+        return Err(io::Error::other("cannot disable the only active monitor"));
+    };
+    let mut changes = Vec::new();
+    if monitor.is_primary() {
+        let (layout, _) = primary_monitor_layout(&replacement, &[monitor], true)?;
+        changes.extend(layout.into_iter().map(|(snapshot, position, flags)| snapshot.into_change(flags, position)));
+    }
+    changes.push(PendingChange::prepare(monitor, 0, |_| DevModeBuilder::from_devmode(detached_dev_mode()))?);
+    // Staged together so a failed detach doesn't leave the primary change queued on its own.
+    stage_changes(&changes, true)?;
+    commit_display_settings()
+}
+
+/// The adapter output matching `display_name` case-insensitively, attached to the desktop or not,
+/// under the exact name Windows reports for it.
+fn find_display_output(display_name: &DeviceName) -> Option<DeviceName> {
+    let mut device: DISPLAY_DEVICEW = unsafe { mem::zeroed() };
+    device.cb = size_of::<DISPLAY_DEVICEW>() as u32;
+    let mut index = 0;
+    while unsafe { EnumDisplayDevicesW(null(), index, &mut device, 0) } != false.into() {
+        let name = decode_wide(&device.DeviceName).to_string_lossy().into_owned();
+        if name.eq_ignore_ascii_case(display_name.as_str()) {
+            return Some(DeviceName::from(name));
+        }
+        index += 1;
+    }
+    None
+}
+
+/// The settings a detached output should be attached with: the mode Windows saved for it, or its
+/// largest mode if none was saved.
+fn attach_dev_mode(wide_name: &[u16]) -> io::Result<DEVMODEW> {
+    let mut devmode = DevModeBuilder::new().build();
+    let found = unsafe { EnumDisplaySettingsW(wide_name.as_ptr(), ENUM_REGISTRY_SETTINGS, &mut devmode) };
+    if found != false.into() && devmode.dmPelsWidth != 0 && devmode.dmPelsHeight != 0 {
+        return Ok(DevModeBuilder::from_devmode(devmode).build());
+    }
+    let largest = collect_display_modes(|mode_number| {
+        let mut devmode = DevModeBuilder::new().build();
+        let found = unsafe { EnumDisplaySettingsExW(wide_name.as_ptr(), mode_number, &mut devmode, 0) };
+        if found == false.into() {
+            None
+        } else {
+            Some(DisplayMode::from(&devmode))
+        }
+    })
+    .into_iter()
+    .max_by_key(|mode| (mode.width as u64 * mode.height as u64, mode.refresh_rate))
+    .ok_or_else(|| {
+        io::Error::other(format!("{}: the output reports no display modes", decode_wide(wide_name).to_string_lossy()))
+    })?;
+    Ok(DevModeBuilder::new()
+        .width(largest.width)
+        .height(largest.height)
+        .refresh_rate(largest.refresh_rate)
+        .bits_per_pixel(largest.bits_per_pixel)
+        .build())
+}
+
+/// Attaches the output `display_name` to the desktop, to the right of the existing monitors, or
+/// detaches it by staging a zero-sized mode. Attaching or detaching an output that already is is a
+/// no-op. Detaching the primary monitor is refused; see `disable_monitor_safely` for a variant that
+/// promotes another monitor first.
+pub fn set_monitor_enabled(display_name: impl Into<DeviceName>, enabled: bool) -> io::Result<()> {
+    let requested = display_name.into();
+    let Some(display_name) = find_display_output(&requested) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no such display output", requested)));
+    };
+    let monitor = monitor_by_name(&display_name);
+    if !enabled {
+        let Some(monitor) = monitor else {
+            return Ok(());
+        };
+        if monitor.is_primary() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: cannot disable the primary monitor; make another monitor primary first", display_name),
+            ));
+        }
+        stage_disable_monitor(&monitor)?;
+        return commit_display_settings();
+    }
+    if monitor.is_some() {
+        return Ok(());
+    }
+    let (origin, size) = virtual_screen_bounds();
+    let devmode = DevModeBuilder::from_devmode(attach_dev_mode(display_name.as_wide())?)
+        .position(origin.x + size.width as i32, origin.y)
+        .build();
+    stage_display_settings(&display_name, &devmode, 0)?;
+    commit_display_settings()
+}
+
+/// Places the monitors in `order` side by side from left to right, top-aligned, in a single commit,
+/// shifted to keep the primary monitor at the desktop origin. `order` must contain every attached
+/// monitor, since one left out would keep its position and could end up overlapping the row.
+pub fn arrange_horizontally(order: &[MonitorHandle]) -> io::Result<()> {
+    for (index, monitor) in order.iter().enumerate() {
+        if order[..index].contains(monitor) {
+            return Err(io::Error::other("a monitor appears more than once in the order"));
+        }
+    }
+    if let Some(missing) = available_monitors().into_iter().find(|monitor| !order.contains(monitor)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: every attached monitor must be in the order", missing.native_identifier()),
+        ));
+    }
+    let configs = order.iter().map(MonitorConfig::capture).collect::<io::Result<Vec<_>>>()?;
+    let mut x = 0;
+    let mut lefts = Vec::with_capacity(configs.len());
+    for config in &configs {
+        lefts.push(x);
+        x += config.size.width as i32;
+    }
+    let origin = configs
+        .iter()
+        .zip(&lefts)
+        .find(|(config, _)| config.primary)
+        .map_or(0, |(_, left)| *left);
+    let changes = order
+        .iter()
+        .zip(&lefts)
+        .map(|(monitor, left)| PendingChange::prepare(monitor, 0, |devmode| devmode.position(left - origin, 0)))
+        .collect::<io::Result<Vec<_>>>()?;
+    stage_changes(&changes, true)?;
+    commit_display_settings()
+}
+
+/// Sets every attached monitor to `width` x `height` in a single commit, keyed by device name
+/// (or by handle, for a monitor whose name can't be read). Monitors that reject the resolution
+/// under `CDS_TEST` are skipped and reported as an error; if the commit itself fails, every staged
+/// monitor reports that error.
+pub fn set_all_resolution(width: u32, height: u32) -> HashMap<String, io::Result<()>> {
+    let mut results = HashMap::new();
+    let mut staged = Vec::new();
+    for monitor in available_monitors() {
+        let key = monitor.result_key();
+        let result = monitor
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))
+            .and_then(|wide_name| {
+                let devmode = DevModeBuilder::from_devmode(get_dev_mode_wide(&wide_name).map_err(io::Error::other)?)
+                    .width(width)
+                    .height(height)
+                    .build();
+                disp_change_result(test_display_settings_wide(&wide_name, &devmode).code())?;
+                stage_display_settings_wide(&wide_name, &devmode, 0)
+            });
+        if result.is_ok() {
+            staged.push(key.clone());
+        }
+        results.insert(key, result);
+    }
+    if staged.is_empty() {
+        return results;
+    }
+    if let Err(err) = commit_display_settings() {
+        for key in staged {
+            let err = match DisplayChangeError::from_io_error(&err) {
+                Some(display_change_error) => display_change_error.into(),
+                None => io::Error::new(err.kind(), err.to_string()),
+            };
+            results.insert(key, Err(err));
+        }
+    }
+    results
+}
+
+/// The settings of one attached display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorConfig {
+    pub device_name: String,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    /// In hertz, as reported by `dmDisplayFrequency`.
+    pub refresh_rate: u32,
+    pub bits_per_pixel: u32,
+    /// One of the `DMDO_*` values.
+    pub orientation: DEVMODE_DISPLAY_ORIENTATION,
+    pub primary: bool,
+}
+
+impl MonitorConfig {
+    fn capture(monitor: &MonitorHandle) -> io::Result<Self> {
+        let wide_name = monitor
+            .wide_name()
+            .ok_or_else(|| io::Error::other("failed to read the monitor's device name"))?;
+        let device_name = decode_wide(&wide_name).to_string_lossy().into_owned();
+        let devmode = get_dev_mode_wide(&wide_name).map_err(io::Error::other)?;
+        let (position, orientation) = unsafe {
+            let display = devmode.Anonymous1.Anonymous2;
+            (display.dmPosition, display.dmDisplayOrientation)
+        };
+        Ok(MonitorConfig {
+            device_name,
+            position: PhysicalPosition { x: position.x, y: position.y },
+            size: PhysicalSize { width: devmode.dmPelsWidth, height: devmode.dmPelsHeight },
+            refresh_rate: devmode.dmDisplayFrequency,
+            bits_per_pixel: devmode.dmBitsPerPel,
+            orientation,
+            primary: monitor.is_primary(),
+        })
+    }
+
+    /// The change that restores this configuration, with the monitor's current settings to roll
+    /// back to. A monitor that is detached now has none, so rolling back detaches it again.
+    fn to_change(&self, flags: CDS_TYPE) -> PendingChange {
+        let wide_name = DeviceName::from(&self.device_name).as_wide().to_vec();
+        let original = get_dev_mode_wide(&wide_name).unwrap_or_else(|_| detached_dev_mode());
+        PendingChange { wide_name, original, devmode: self.to_dev_mode(), flags }
+    }
+
+    fn to_dev_mode(&self) -> DEVMODEW {
+        DevModeBuilder::new()
+            .position(self.position.x, self.position.y)
+            .width(self.size.width)
+            .height(self.size.height)
+            .refresh_rate(self.refresh_rate)
+            .bits_per_pixel(self.bits_per_pixel)
+            .orientation(self.orientation)
+            .build()
+    }
+}
+
+/// A snapshot of the layout and mode of every attached display.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DisplayConfig {
+    pub monitors: Vec<MonitorConfig>,
+}
+
+impl DisplayConfig {
+    pub fn capture() -> io::Result<Self> {
+        let monitors = available_monitors()
+            .iter()
+            .map(MonitorConfig::capture)
+            .collect::<io::Result<_>>()?;
+        Ok(DisplayConfig { monitors })
+    }
+
+    /// Applies every monitor's settings in a single commit. If staging fails partway, the monitors
+    /// already staged are restored so nothing is left queued for a later commit.
+    pub fn apply(&self) -> io::Result<()> {
+        // Like set_primary_monitor, queue the primary last so its CDS_SET_PRIMARY change wins.
+        let (primary, others): (Vec<_>, Vec<_>) = self.monitors.iter().partition(|monitor| monitor.primary);
+        let changes: Vec<_> = others
+            .into_iter()
+            .map(|monitor| monitor.to_change(0))
+            .chain(primary.into_iter().map(|monitor| monitor.to_change(CDS_SET_PRIMARY)))
+            .collect();
+        stage_changes(&changes, true)?;
+        commit_display_settings()
+    }
+}
+
+/// Detects display changes by comparing snapshots, for callers that would rather poll than run a
+/// watcher thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayChangeMonitor {
+    last: (Option<DisplayConfig>, Vec<Option<u32>>),
+}
+
+impl DisplayChangeMonitor {
+    fn snapshot() -> (Option<DisplayConfig>, Vec<Option<u32>>) {
+        let dpis = available_monitors().iter().map(|monitor| get_monitor_dpi(monitor.0)).collect();
+        (DisplayConfig::capture().ok(), dpis)
+    }
+
+    pub fn new() -> Self {
+        DisplayChangeMonitor { last: Self::snapshot() }
+    }
+
+    /// Whether the layout, modes or DPI of any monitor changed since the last call (or since
+    /// `new`, for the first call).
+    pub fn poll(&mut self) -> bool {
+        let current = Self::snapshot();
+        let changed = current != self.last;
+        self.last = current;
+        changed
+    }
+}
+
+impl Default for DisplayChangeMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RevertState {
+    Pending,
+    Confirmed,
+    Reverted,
+}
+
+/// A revert to a previous `DisplayConfig` that runs on a background thread once the timeout
+/// elapses, unless it is confirmed first.
+pub struct PendingRevert {
+    state: Arc<(Mutex<RevertState>, Condvar)>,
+    previous: DisplayConfig,
+}
+
+impl PendingRevert {
+    pub fn schedule(previous: DisplayConfig, timeout: Duration) -> Self {
+        let state = Arc::new((Mutex::new(RevertState::Pending), Condvar::new()));
+        let thread_state = Arc::clone(&state);
+        let thread_previous = previous.clone();
+        thread::spawn(move || {
+            let (lock, condvar) = &*thread_state;
+            let guard = lock.lock().unwrap();
+            let (mut guard, _) = condvar
+                .wait_timeout_while(guard, timeout, |state| *state == RevertState::Pending)
+                .unwrap();
+            if *guard == RevertState::Pending {
+                *guard = RevertState::Reverted;
+                if let Err(err) = thread_previous.apply() {
+                    tracing::warn!("failed to revert display configuration: {}", err);
+                }
+            }
+        });
+        PendingRevert { state, previous }
+    }
+
+    /// Keeps the current configuration. Returns `false` if the revert already happened.
+    pub fn confirm(&self) -> bool {
+        self.transition(RevertState::Confirmed) || *self.state.0.lock().unwrap() == RevertState::Confirmed
+    }
+
+    /// Reverts now instead of waiting for the timeout. Does nothing if the change was already
+    /// confirmed or reverted.
+    pub fn revert(&self) -> io::Result<()> {
+        if self.transition(RevertState::Reverted) {
+            self.previous.apply()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether the change is still waiting to be confirmed or reverted.
+    pub fn is_pending(&self) -> bool {
+        *self.state.0.lock().unwrap() == RevertState::Pending
+    }
+
+    /// Moves a pending change to `to`, returning whether this call was the one that did.
+    fn transition(&self, to: RevertState) -> bool {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        if *state != RevertState::Pending {
+            return false;
+        }
+        *state = to;
+        condvar.notify_all();
+        true
+    }
+}
+
+/// Applies `config`, then keeps it only if `confirm` returns `true` before `timeout` elapses;
+/// otherwise the configuration that was active beforehand is restored.
+///
+/// `confirm` runs on the calling thread while the timeout is tracked on a background thread, so a
+/// `confirm` that blocks past the timeout still results in a revert. Returns whether the new
+/// configuration was kept.
+pub fn apply_with_confirmation<F>(config: &DisplayConfig, timeout: Duration, confirm: F) -> io::Result<bool>
+where
+    F: FnOnce() -> bool,
+{
+    let previous = DisplayConfig::capture()?;
+    config.apply()?;
+    let pending = PendingRevert::schedule(previous, timeout);
+    if confirm() && pending.confirm() {
+        Ok(true)
+    } else {
+        pending.revert()?;
+        Ok(false)
+    }
+}
+
+/// Sets `monitor` to `width` x `height` and restores its previous mode after `timeout` unless the
+/// returned `PendingRevert` is confirmed first, like the "Keep these display settings?" prompt.
+pub fn set_mode_with_confirmation(
+    monitor: &MonitorHandle,
+    width: u32,
+    height: u32,
+    timeout: Duration,
+) -> io::Result<PendingRevert> {
+    let previous = DisplayConfig { monitors: vec![MonitorConfig::capture(monitor)?] };
+    monitor.set_mode(width, height, true)?;
+    Ok(PendingRevert::schedule(previous, timeout))
+}
+
+
+// Taskbar
+
+/// An edge of a monitor that the taskbar can be docked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScreenEdge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+impl ScreenEdge {
+    fn abe(self) -> u32 {
+        match self {
+            ScreenEdge::Left => ABE_LEFT,
+            ScreenEdge::Top => ABE_TOP,
+            ScreenEdge::Right => ABE_RIGHT,
+            ScreenEdge::Bottom => ABE_BOTTOM,
+        }
+    }
+}
+
+impl FromStr for ScreenEdge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" => Ok(ScreenEdge::Left),
+            "top" => Ok(ScreenEdge::Top),
+            "right" => Ok(ScreenEdge::Right),
+            "bottom" => Ok(ScreenEdge::Bottom),
+            _ => Err(format!("invalid edge {:?}; expected 'left', 'top', 'right' or 'bottom'", s)),
+        }
+    }
+}
+
+/// Moves the primary taskbar (`Shell_TrayWnd`) to `edge` of `monitor`, keeping its thickness.
+pub fn set_taskbar_monitor(monitor: &MonitorHandle, edge: ScreenEdge) -> io::Result<()> {
+    let class_name = wide_string("Shell_TrayWnd");
+    let taskbar = unsafe { FindWindowW(class_name.as_ptr(), null()) };
+    if taskbar == 0 {
+        return Err(io::Error::other("the taskbar window (Shell_TrayWnd) was not found"));
+    }
+
+    let mut data: APPBARDATA = unsafe { mem::zeroed() };
+    data.cbSize = size_of::<APPBARDATA>() as u32;
+    data.hWnd = taskbar;
+    if unsafe { SHAppBarMessage(ABM_GETTASKBARPOS, &mut data) } == 0 {
+        return Err(io::Error::other("failed to query the taskbar position"));
+    }
+    let current = data.rc;
+    let thickness = (current.right - current.left).min(current.bottom - current.top);
+
+    let info = get_monitor_info(monitor.0)?;
+    // The work area excludes the taskbar itself, so when it already sits on the target monitor
+    // the full monitor rectangle is the area it is being placed in.
+    let currently_on_target = unsafe { MonitorFromRect(&current, MONITOR_DEFAULTTONEAREST) } == monitor.0;
+    let area = if currently_on_target { info.monitorInfo.rcMonitor } else { info.monitorInfo.rcWork };
+    data.uEdge = edge.abe();
+    data.rc = match edge {
+        ScreenEdge::Left => RECT { right: area.left + thickness, ..area },
+        ScreenEdge::Top => RECT { bottom: area.top + thickness, ..area },
+        ScreenEdge::Right => RECT { left: area.right - thickness, ..area },
+        ScreenEdge::Bottom => RECT { top: area.bottom - thickness, ..area },
+    };
+
+    // ABM_SETPOS may adjust the rectangle, but it only reserves the space; the window itself still
+    // has to be moved there.
+    unsafe { SHAppBarMessage(ABM_SETPOS, &mut data) };
+    let rc = data.rc;
+    let moved = unsafe {
+        SetWindowPos(
+            taskbar,
+            0,
+            rc.left,
+            rc.top,
+            rc.right - rc.left,
+            rc.bottom - rc.top,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        )
+    };
+    if moved == false.into() {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+
+// Python bindings
+
+create_exception!(wmutil, MonitorNotFoundError, PyValueError, "No attached monitor matches the given name.");
+create_exception!(
+    wmutil,
+    InvalidMonitorHandleError,
+    MonitorNotFoundError,
+    "The monitor handle no longer refers to an attached monitor."
+);
+
+create_exception!(
+    wmutil,
+    BrightnessNotSupportedError,
+    PyOSError,
+    "The monitor's brightness can't be controlled over DDC/CI."
+);
+
+create_exception!(
+    wmutil,
+    DisplaySettingsError,
+    PyOSError,
+    "Windows rejected a display settings change; the message names the DISP_CHANGE_* code."
+);
+
+fn monitor_not_found(display_name: &str) -> PyErr {
+    MonitorNotFoundError::new_err(format!("Monitor with name {:?} not found", display_name))
+}
+
+/// Raises `DisplaySettingsError` for a rejected display settings change and `OSError` otherwise.
+fn display_change_py_err(err: io::Error) -> PyErr {
+    if DisplayChangeError::from_io_error(&err).is_some() {
+        DisplaySettingsError::new_err(err.to_string())
+    } else {
+        err.into()
+    }
+}
+
+#[pyclass(module = "wmutil")]
+#[derive(Clone)]
+struct Monitor {
+    monitor_handle: MonitorHandle,
+    /// Captured when the object is created so `refresh` can find the monitor again after its
+    /// handle has gone stale.
+    device_name: Option<String>,
+}
+
+impl Monitor {
+    fn new(monitor_handle: MonitorHandle) -> Self {
+        let device_name = monitor_handle.name();
+        Monitor { monitor_handle, device_name }
+    }
+
+    /// The handle, or `InvalidMonitorHandleError` if it has gone stale.
+    fn valid_handle(&self) -> PyResult<&MonitorHandle> {
+        if self.monitor_handle.is_valid() {
+            Ok(&self.monitor_handle)
+        } else {
+            Err(InvalidMonitorHandleError::new_err(format!(
+                "the handle for monitor {:?} is no longer valid; call refresh() to re-resolve it",
+                self.device_name.as_deref().unwrap_or_default()
+            )))
+        }
+    }
+}
+
+#[pymethods]
+impl Monitor {
+    #[getter]
+    fn name(&self) -> String {
+        self.monitor_handle.name().unwrap_or(String::from("Unknown monitor name"))
+    }
+
+    #[getter]
+    fn size(&self) -> PyResult<(u32, u32)> {
+        let size = self.valid_handle()?.size();
         let width = size.width;
         let height = size.height;
-        (width, height)
+        Ok((width, height))
+    }
+
+    /// The size in device-independent pixels, rounded to the nearest pixel.
+    #[getter]
+    fn logical_size(&self) -> PyResult<(u32, u32)> {
+        let size = self.valid_handle()?.logical_size();
+        Ok((size.width.round() as u32, size.height.round() as u32))
+    }
+
+    #[getter]
+    fn position(&self) -> PyResult<(i32, i32)> {
+        let position = self.valid_handle()?.position();
+        let x_pos = position.x;
+        let y_pos = position.y;
+        Ok((x_pos, y_pos))
+    }
+
+    #[getter]
+    fn work_area(&self) -> PyResult<((i32, i32), (u32, u32))> {
+        let (position, size) = self.valid_handle()?.work_area();
+        Ok(((position.x, position.y), (size.width, size.height)))
+    }
+
+    #[getter]
+    fn center(&self) -> PyResult<(i32, i32)> {
+        let center = self.valid_handle()?.center();
+        Ok((center.x, center.y))
+    }
+
+    #[getter]
+    fn is_valid(&self) -> bool {
+        self.monitor_handle.is_valid()
+    }
+
+    /// The top-left corner that centers a `width` x `height` window in the work area.
+    pub fn center_rect(&self, width: u32, height: u32) -> PyResult<(i32, i32)> {
+        let position = self.valid_handle()?.center_rect(PhysicalSize::new(width, height));
+        Ok((position.x, position.y))
+    }
+
+    #[getter]
+    fn scale_factor(&self) -> PyResult<f64> {
+        Ok(self.valid_handle()?.scale_factor())
+    }
+
+    #[pyo3(signature = (dpi_type="effective"))]
+    pub fn get_dpi(&self, dpi_type: &str) -> PyResult<Option<u32>> {
+        let dpi_type: DpiType = dpi_type.parse().map_err(PyValueError::new_err)?;
+        Ok(get_monitor_dpi_for_type(self.valid_handle()?.0, dpi_type))
+    }
+
+    #[getter]
+    fn refresh_rate_millihertz(&self) -> PyResult<Option<u32>> {
+        Ok(self.valid_handle()?.refresh_rate_millihertz())
+    }
+
+    #[getter]
+    fn refresh_rate_hz(&self) -> PyResult<Option<f64>> {
+        Ok(self.valid_handle()?.refresh_rate_hz())
+    }
+
+    #[getter]
+    fn refresh_rate_rational(&self) -> PyResult<Option<(u32, u32)>> {
+        Ok(self.valid_handle()?.refresh_rate_rational())
+    }
+
+    #[getter]
+    fn bits_per_pixel(&self) -> PyResult<Option<u32>> {
+        Ok(self.valid_handle()?.bits_per_pixel())
+    }
+
+    /// The raw `MONITORINFO.dwFlags` bits, including any `flags` doesn't have a name for.
+    #[getter]
+    fn raw_flags(&self) -> PyResult<u32> {
+        Ok(self.valid_handle()?.flags())
+    }
+
+    #[getter]
+    fn flags(&self) -> PyResult<HashMap<&'static str, bool>> {
+        let flags = self.valid_handle()?.flags();
+        Ok(HashMap::from([
+            ("primary", has_flag(flags, MONITORINFOF_PRIMARY)),
+        ]))
+    }
+
+    #[getter]
+    fn orientation(&self) -> PyResult<&'static str> {
+        Ok(self.valid_handle()?.orientation().as_str())
+    }
+
+    #[getter]
+    fn orientation_degrees(&self) -> PyResult<u32> {
+        Ok(self.valid_handle()?.orientation_degrees())
+    }
+
+    #[getter]
+    fn physical_size_mm(&self) -> PyResult<Option<(u32, u32)>> {
+        Ok(self.valid_handle()?.physical_size_mm())
+    }
+
+    #[getter]
+    fn is_primary(&self) -> PyResult<bool> {
+        Ok(self.valid_handle()?.is_primary())
+    }
+
+    #[getter]
+    fn friendly_name(&self) -> PyResult<Option<String>> {
+        Ok(self.valid_handle()?.friendly_name())
+    }
+
+    /// The current DDC/CI brightness, or `None` if the monitor doesn't support it.
+    #[getter]
+    fn brightness(&self) -> PyResult<Option<u32>> {
+        Ok(self.valid_handle()?.get_brightness().map(|(_, current, _)| current))
+    }
+
+    #[setter]
+    fn set_brightness(&self, value: u32) -> PyResult<()> {
+        match self.valid_handle()?.set_brightness(value) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => Err(BrightnessNotSupportedError::new_err(err.to_string())),
+            Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The DDC/CI brightness as `(min, current, max)`, or `None` if the monitor doesn't support it.
+    #[getter]
+    fn brightness_range(&self) -> PyResult<Option<(u32, u32, u32)>> {
+        Ok(self.valid_handle()?.get_brightness())
+    }
+
+    #[getter]
+    fn is_internal(&self) -> PyResult<bool> {
+        Ok(self.valid_handle()?.is_internal())
+    }
+
+    #[getter]
+    fn connection_type(&self) -> PyResult<&'static str> {
+        Ok(self.valid_handle()?.connection_type())
+    }
+
+    #[getter]
+    fn is_low_bit_depth(&self) -> PyResult<bool> {
+        Ok(self.valid_handle()?.is_low_bit_depth())
+    }
+
+    #[getter]
+    fn hdr_supported(&self) -> PyResult<bool> {
+        Ok(self.valid_handle()?.hdr_supported())
+    }
+
+    #[getter]
+    fn hdr_enabled(&self) -> PyResult<bool> {
+        Ok(self.valid_handle()?.hdr_enabled())
+    }
+
+    #[getter]
+    fn serial_number(&self) -> PyResult<Option<String>> {
+        Ok(self.valid_handle()?.serial_number())
+    }
+
+    #[getter]
+    fn manufacturer_id(&self) -> PyResult<Option<String>> {
+        Ok(self.valid_handle()?.manufacturer_id())
+    }
+
+    #[getter]
+    fn product_code(&self) -> PyResult<Option<u16>> {
+        Ok(self.valid_handle()?.product_code())
+    }
+
+    #[getter]
+    fn edid<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        Ok(self.valid_handle()?.edid().map(|edid| PyBytes::new_bound(py, &edid)))
+    }
+
+    #[getter]
+    fn adapter_name(&self) -> PyResult<Option<String>> {
+        Ok(self.valid_handle()?.adapter_name())
+    }
+
+    #[getter]
+    fn display_number(&self) -> PyResult<Option<u32>> {
+        Ok(self.valid_handle()?.display_number())
+    }
+
+    #[getter]
+    fn stable_id(&self) -> PyResult<Option<String>> {
+        Ok(self.valid_handle()?.stable_id())
+    }
+
+    #[getter]
+    fn scale_is_recommended(&self) -> PyResult<Option<bool>> {
+        Ok(self.valid_handle()?.scale_is_recommended())
+    }
+
+    #[getter]
+    fn handle(&self) -> isize {
+        self.monitor_handle.0 as isize
+    }
+
+    pub fn get_display_modes(&self) -> PyResult<Vec<PyDisplayMode>> {
+        let modes = self.valid_handle()?.display_modes();
+        Ok(modes.into_iter().map(|mode| PyDisplayMode { mode }).collect())
+    }
+
+    #[pyo3(signature = (min_width=0, min_height=0, min_refresh=0))]
+    pub fn supported_modes(&self, min_width: u32, min_height: u32, min_refresh: u32) -> PyResult<Vec<PyDisplayMode>> {
+        let modes = self.valid_handle()?.supported_modes_filtered(min_width, min_height, min_refresh);
+        Ok(modes.into_iter().map(|mode| PyDisplayMode { mode }).collect())
+    }
+
+    pub fn refresh_summary(&self) -> PyResult<Option<HashMap<&'static str, u32>>> {
+        let summary = self.valid_handle()?.refresh_summary();
+        Ok(summary.map(|summary| {
+            HashMap::from([
+                ("current", summary.current),
+                ("max_at_current_res", summary.max_at_current_res),
+                ("max_overall", summary.max_overall),
+            ])
+        }))
+    }
+
+    #[pyo3(signature = (width, height, refresh=None))]
+    pub fn supports_mode(&self, width: u32, height: u32, refresh: Option<u32>) -> PyResult<bool> {
+        Ok(self.valid_handle()?.supports_mode(width, height, refresh))
+    }
+
+    pub fn reset_scale_to_recommended(&self) -> PyResult<()> {
+        Ok(self.valid_handle()?.reset_scale_to_recommended()?)
+    }
+
+    /// The current, recommended and available scale percentages, or `None` if Windows doesn't
+    /// answer the undocumented scaling query.
+    pub fn scale_info<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let Some(info) = self.valid_handle()?.scale_info() else {
+            return Ok(None);
+        };
+        let dict = PyDict::new_bound(py);
+        dict.set_item("current", info.current)?;
+        dict.set_item("recommended", info.recommended)?;
+        dict.set_item("available", info.available)?;
+        Ok(Some(dict))
+    }
+
+    /// The gamma ramp as three lists (red, green, blue) of 256 levels each.
+    pub fn get_gamma_ramp(&self) -> PyResult<Vec<Vec<u16>>> {
+        let ramp = self.valid_handle()?.get_gamma_ramp()?;
+        Ok(ramp.iter().map(|channel| channel.to_vec()).collect())
+    }
+
+    pub fn set_gamma_ramp(&self, ramp: Vec<Vec<u16>>) -> PyResult<()> {
+        if ramp.len() != 3 {
+            return Err(PyValueError::new_err(format!(
+                "a gamma ramp needs 3 channels (red, green, blue), not {}",
+                ramp.len()
+            )));
+        }
+        let mut gamma_ramp: GammaRamp = [[0; 256]; 3];
+        for (channel, values) in gamma_ramp.iter_mut().zip(&ramp) {
+            *channel = values.as_slice().try_into().map_err(|_| {
+                PyValueError::new_err(format!("each gamma ramp channel needs 256 values, not {}", values.len()))
+            })?;
+        }
+        Ok(self.valid_handle()?.set_gamma_ramp(&gamma_ramp)?)
+    }
+
+    /// The main properties in one call. Pairs are lists rather than tuples so the dict can be
+    /// passed straight to `json.dumps`.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        monitor_info_to_dict(py, &self.valid_handle()?.info()?)
+    }
+
+    /// Re-resolves the handle by device name, for when the display configuration has changed
+    /// since this object was created.
+    pub fn refresh(&mut self) -> PyResult<()> {
+        let device_name = self.device_name.as_deref().unwrap_or_default();
+        let monitor_handle = monitor_by_name(device_name)
+            .ok_or_else(|| monitor_not_found(device_name))?;
+        self.monitor_handle = monitor_handle;
+        Ok(())
+    }
+
+    pub fn set_primary(&self, py: Python) -> PyResult<()> {
+        py_set_primary_monitor(py, self.name(), false, true)?;
+        Ok(())
+    }
+
+    /// Pickles the handle and unpickles through `get_monitor_from_handle`. Handles are only
+    /// meaningful on the same machine and session, and until the next display change, so
+    /// unpickling anywhere else raises `InvalidMonitorHandleError` (or finds the wrong monitor).
+    pub fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (isize,))> {
+        let constructor = py.import_bound("wmutil")?.getattr("get_monitor_from_handle")?;
+        Ok((constructor, (self.handle(),)))
+    }
+
+    /// Equality and hashing go by handle, which Windows may hand to a different monitor after a
+    /// display change. Key long-lived caches on `stable_id` instead.
+    pub fn __hash__(&self) -> isize {
+        self.handle()
+    }
+
+    pub fn __eq__(&self, other: &Self) -> bool {
+        if self.handle() == other.handle() {
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn __repr__(&self) -> PyResult<String> {
+        let name = self
+            .monitor_handle
+            .name()
+            .or_else(|| self.device_name.clone())
+            .unwrap_or_else(|| String::from("unknown"));
+        if self.monitor_handle.is_valid() {
+            let size = self.monitor_handle.size();
+            Ok(format!("<wmutil.Monitor '{}' {}x{} @handle={}>", name, size.width, size.height, self.handle()))
+        } else {
+            Ok(format!("<wmutil.Monitor '{}' (invalid) @handle={}>", name, self.handle()))
+        }
+    }
+}
+
+#[pyclass(name = "DisplayMode", module = "wmutil", frozen)]
+#[derive(Clone)]
+struct PyDisplayMode {
+    mode: DisplayMode,
+}
+
+#[pymethods]
+impl PyDisplayMode {
+    #[getter]
+    fn width(&self) -> u32 {
+        self.mode.width
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.mode.height
+    }
+
+    #[getter]
+    fn refresh_rate(&self) -> u32 {
+        self.mode.refresh_rate
+    }
+
+    #[getter]
+    fn bits_per_pixel(&self) -> u32 {
+        self.mode.bits_per_pixel
+    }
+
+    pub fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.mode.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn __eq__(&self, other: &Self) -> bool {
+        self.mode == other.mode
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<wmutil.DisplayMode {}x{} @ {}Hz, {} bpp>",
+            self.mode.width, self.mode.height, self.mode.refresh_rate, self.mode.bits_per_pixel
+        )
+    }
+}
+
+#[pyfunction]
+fn get_primary_monitor() -> Monitor {
+    let handle = primary_monitor();
+    Monitor::new(handle)
+}
+
+#[pyfunction]
+fn get_window_monitor(hwnd: isize) -> Monitor {
+    let handle = current_monitor(hwnd.into());
+    Monitor::new(handle)
+}
+
+#[pyfunction]
+#[pyo3(name = "maximized_bounds_for_window")]
+fn py_maximized_bounds_for_window(hwnd: isize) -> (Monitor, (i32, i32, i32, i32)) {
+    let (monitor, rect) = maximized_bounds_for_window(hwnd);
+    (Monitor::new(monitor), (rect.left, rect.top, rect.right, rect.bottom))
+}
+
+#[pyfunction]
+#[pyo3(name = "desktop_is_rectangular")]
+fn py_desktop_is_rectangular() -> bool {
+    desktop_is_rectangular()
+}
+
+#[pyfunction]
+#[pyo3(name = "monitor_in_direction")]
+fn py_monitor_in_direction(monitor: &Monitor, direction: &str) -> PyResult<Option<Monitor>> {
+    let direction: Direction = direction.parse().map_err(PyValueError::new_err)?;
+    Ok(monitor_in_direction(&monitor.monitor_handle, direction).map(Monitor::new))
+}
+
+/// Maps each monitor's device name to its neighbors' device names (or `None`) by direction.
+#[pyfunction]
+#[pyo3(name = "monitor_adjacency")]
+fn py_monitor_adjacency() -> HashMap<String, HashMap<&'static str, Option<String>>> {
+    monitor_adjacency()
+        .into_iter()
+        .map(|(monitor, neighbors)| {
+            let neighbors = Direction::ALL
+                .into_iter()
+                .map(|direction| (direction.as_str(), neighbors.get(&direction).and_then(MonitorHandle::name)))
+                .collect();
+            (monitor.name().unwrap_or_default(), neighbors)
+        })
+        .collect()
+}
+
+#[pyfunction]
+fn get_virtual_screen_bounds() -> ((i32, i32), (u32, u32)) {
+    let (position, size) = virtual_screen_bounds();
+    ((position.x, position.y), (size.width, size.height))
+}
+
+#[pyfunction]
+#[pyo3(name = "monitor_from_rect")]
+fn py_monitor_from_rect(x: i32, y: i32, width: u32, height: u32) -> Monitor {
+    Monitor::new(monitor_from_rect(x, y, width, height))
+}
+
+#[pyfunction]
+#[pyo3(name = "monitor_at_virtual_pixel")]
+fn py_monitor_at_virtual_pixel(vx: i32, vy: i32) -> Option<Monitor> {
+    monitor_at_virtual_pixel(vx, vy).map(Monitor::new)
+}
+
+#[pyfunction]
+#[pyo3(name = "monitor_rows")]
+fn py_monitor_rows() -> Vec<Vec<Monitor>> {
+    monitor_rows()
+        .into_iter()
+        .map(|row| row.into_iter().map(Monitor::new).collect())
+        .collect()
+}
+
+#[pyfunction]
+fn get_layout_diagram() -> String {
+    layout_diagram()
+}
+
+/// Monitors come back in GDI order unless `sort` is `"position"`, which orders them left to right.
+/// Mirroring drivers and displays that aren't part of the desktop are left out unless
+/// `include_pseudo` is set.
+#[pyfunction]
+#[pyo3(signature = (sort=None, include_pseudo=false))]
+fn enumerate_monitors(sort: Option<&str>, include_pseudo: bool) -> PyResult<Vec<Monitor>> {
+    let handles = match sort {
+        None => monitors().collect(),
+        Some("position") => monitors_by_position(),
+        Some(other) => {
+            return Err(PyValueError::new_err(format!("invalid sort {:?}; expected 'position' or None", other)))
+        }
+    };
+    let mut monitors: Vec<Monitor> = Vec::new();
+    for monitor in handles {
+        if include_pseudo || !monitor.is_pseudo() {
+            monitors.push(Monitor::new(monitor))
+        }
+    }
+    Ok(monitors)
+}
+
+/// `default` picks what to return for a point outside every monitor: the primary monitor, the
+/// nearest monitor, or `None`.
+#[pyfunction]
+#[pyo3(signature = (x, y, default="primary"))]
+fn get_monitor_from_point(x: i32, y: i32, default: &str) -> PyResult<Option<Monitor>> {
+    let fallback: MonitorFallback = default.parse().map_err(PyValueError::new_err)?;
+    Ok(monitor_from_point(x, y, fallback).map(Monitor::new))
+}
+
+#[pyfunction]
+fn get_monitor_from_handle(handle: isize) -> PyResult<Monitor> {
+    let monitor_handle = MonitorHandle::new(handle);
+    if !monitor_handle.is_valid() {
+        return Err(InvalidMonitorHandleError::new_err(format!(
+            "{:#x} is not a handle to an attached monitor",
+            handle
+        )));
+    }
+    Ok(Monitor::new(monitor_handle))
+}
+
+#[pyfunction]
+fn get_monitor_by_name(name: &str) -> PyResult<Monitor> {
+    monitor_by_name(name).map(Monitor::new).ok_or_else(|| monitor_not_found(name))
+}
+
+/// One of "extend", "clone", "internal", "external" or "mixed".
+#[pyfunction]
+fn get_display_topology() -> PyResult<&'static str> {
+    Ok(display_topology()?.as_str())
+}
+
+/// Returns `True` once the topology has been switched; failures raise.
+#[pyfunction]
+#[pyo3(name = "set_display_topology")]
+fn py_set_display_topology(mode: &str) -> PyResult<bool> {
+    let topology: DisplayTopology = mode.parse().map_err(PyValueError::new_err)?;
+    match set_display_topology(topology) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Like `Monitor.center_rect`, on `monitor` or else the primary monitor.
+#[pyfunction]
+#[pyo3(name = "center_rect", signature = (width, height, monitor=None))]
+fn py_center_rect(width: u32, height: u32, monitor: Option<&Monitor>) -> PyResult<(i32, i32)> {
+    match monitor {
+        Some(monitor) => monitor.center_rect(width, height),
+        None => Monitor::new(primary_monitor()).center_rect(width, height),
+    }
+}
+
+#[pyfunction]
+fn get_monitor_by_number(number: u32) -> PyResult<Monitor> {
+    monitor_by_number(number)
+        .map(Monitor::new)
+        .ok_or_else(|| MonitorNotFoundError::new_err(format!("Monitor with number {} not found", number)))
+}
+
+#[pyfunction]
+fn get_monitor_from_cursor() -> Monitor {
+    Monitor::new(monitor_from_cursor())
+}
+
+#[pyfunction]
+#[pyo3(name = "monitor_for_points")]
+fn py_monitor_for_points(points: Vec<(i32, i32)>) -> PyResult<Monitor> {
+    monitor_for_points(&points)
+        .map(Monitor::new)
+        .ok_or_else(|| PyValueError::new_err("points must not be empty"))
+}
+
+/// Sets one monitor's scale percentage through the undocumented interface the Settings app uses.
+#[pyfunction]
+fn set_scale_factor(display_name: String, percent: u32) -> PyResult<()> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    match monitor.set_scale_percent(percent) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Maps each monitor to `None` if it was set to `percent`, or to the error message if not.
+#[pyfunction]
+#[pyo3(name = "set_all_scale_percent")]
+fn py_set_all_scale_percent(percent: u32) -> HashMap<String, Option<String>> {
+    set_all_scale_percent(percent)
+        .into_iter()
+        .map(|(key, result)| (key, result.err().map(|err| err.to_string())))
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "arrange_horizontally")]
+fn py_arrange_horizontally(order: Vec<PyRef<Monitor>>) -> PyResult<()> {
+    let order: Vec<MonitorHandle> = order.iter().map(|monitor| monitor.monitor_handle.clone()).collect();
+    match arrange_horizontally(&order) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
+    }
+}
+
+/// Maps each monitor to `None` if it was set to the resolution, or to the error message if not.
+#[pyfunction]
+#[pyo3(name = "set_all_resolution")]
+fn py_set_all_resolution(width: u32, height: u32) -> HashMap<String, Option<String>> {
+    set_all_resolution(width, height)
+        .into_iter()
+        .map(|(key, result)| (key, result.err().map(|err| err.to_string())))
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "suggest_primary_monitor")]
+fn py_suggest_primary_monitor() -> Monitor {
+    Monitor::new(suggest_primary_monitor())
+}
+
+#[pyfunction]
+#[pyo3(name = "desktop_pixel_format")]
+fn py_desktop_pixel_format(py: Python) -> PyResult<Option<Bound<PyDict>>> {
+    let Some(format) = desktop_pixel_format() else {
+        return Ok(None);
+    };
+    let dict = PyDict::new_bound(py);
+    dict.set_item("bits_per_pixel", format.bits_per_pixel())?;
+    dict.set_item("channel_order", format.channel_order())?;
+    Ok(Some(dict))
+}
+
+#[pyfunction]
+#[pyo3(name = "largest_monitor")]
+fn py_largest_monitor() -> Monitor {
+    Monitor::new(largest_monitor())
+}
+
+#[pyfunction]
+#[pyo3(name = "highest_resolution_monitor")]
+fn py_highest_resolution_monitor() -> Monitor {
+    Monitor::new(highest_resolution_monitor())
+}
+
+#[pyfunction]
+#[pyo3(name = "dpi_diagnostics")]
+fn py_dpi_diagnostics(py: Python) -> PyResult<Bound<PyDict>> {
+    let diagnostics = dpi_diagnostics();
+    let monitors = diagnostics
+        .monitors
+        .iter()
+        .map(|monitor| {
+            let dict = PyDict::new_bound(py);
+            dict.set_item("name", monitor.monitor.name())?;
+            dict.set_item("effective", monitor.effective)?;
+            dict.set_item("angular", monitor.angular)?;
+            dict.set_item("raw", monitor.raw)?;
+            Ok(dict)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("awareness", diagnostics.awareness.as_str())?;
+    dict.set_item("system_dpi", diagnostics.system_dpi)?;
+    dict.set_item("monitors", monitors)?;
+    Ok(dict)
+}
+
+fn warn_dpi_awareness_unsupported(py: Python, function: &str) -> PyResult<()> {
+    PyErr::warn_bound(
+        py,
+        &py.get_type_bound::<PyRuntimeWarning>(),
+        &format!("{} is unavailable on this version of Windows; DPI awareness left unchanged", function),
+        1,
+    )
+}
+
+/// Returns `False`, with a warning, on versions of Windows that can't set the awareness.
+#[pyfunction]
+#[pyo3(name = "set_process_dpi_awareness")]
+fn py_set_process_dpi_awareness(py: Python, level: &str) -> PyResult<bool> {
+    let awareness: DpiAwareness = level.parse().map_err(PyValueError::new_err)?;
+    let changed = set_process_dpi_awareness(awareness)?;
+    if !changed {
+        warn_dpi_awareness_unsupported(py, "SetProcessDpiAwarenessContext")?;
+    }
+    Ok(changed)
+}
+
+/// Overrides the calling thread's DPI awareness inside a `with` block. Unsendable because the
+/// override belongs to the thread that entered the block.
+#[pyclass(name = "DpiAwarenessContext", module = "wmutil", unsendable)]
+struct PyDpiAwarenessContext {
+    awareness: DpiAwareness,
+    guard: Option<DpiAwarenessGuard>,
+}
+
+#[pymethods]
+impl PyDpiAwarenessContext {
+    #[getter]
+    fn level(&self) -> &'static str {
+        self.awareness.as_str()
+    }
+
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        if slf.guard.is_none() {
+            slf.guard = set_thread_dpi_awareness(slf.awareness)?;
+            if slf.guard.is_none() {
+                warn_dpi_awareness_unsupported(slf.py(), "SetThreadDpiAwarenessContext")?;
+            }
+        }
+        Ok(slf)
+    }
+
+    fn __exit__(&mut self, _exc_type: PyObject, _exc_value: PyObject, _traceback: PyObject) {
+        self.guard = None;
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (level="per_monitor_aware_v2"))]
+fn dpi_awareness(level: &str) -> PyResult<PyDpiAwarenessContext> {
+    let awareness: DpiAwareness = level.parse().map_err(PyValueError::new_err)?;
+    Ok(PyDpiAwarenessContext { awareness, guard: None })
+}
+
+#[pyfunction]
+#[pyo3(name = "is_remote_session")]
+fn py_is_remote_session() -> bool {
+    is_remote_session()
+}
+
+#[pyfunction]
+#[pyo3(name = "refresh_rate_groups")]
+fn py_refresh_rate_groups() -> HashMap<u32, Vec<Monitor>> {
+    refresh_rate_groups()
+        .into_iter()
+        .map(|(refresh_rate, monitors)| {
+            let monitors = monitors.into_iter().map(Monitor::new).collect();
+            (refresh_rate, monitors)
+        })
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "refresh_rates_uniform")]
+fn py_refresh_rates_uniform() -> bool {
+    refresh_rates_uniform()
+}
+
+fn wide_string(s: &str) -> Vec<u16> {
+    let mut vec: Vec<u16> = s.encode_utf16().collect();
+    vec.push(0);
+    vec
+}
+
+
+/// With `test_only`, the status string of a `CDS_TEST` dry run; otherwise `True` once the change
+/// is applied, raising `DisplaySettingsError` if Windows rejects it. With `persist=False` the
+/// change is lost at the next reboot or sign-out.
+#[pyfunction]
+#[pyo3(name = "set_primary_monitor", signature = (display_name, test_only=false, persist=true))]
+fn py_set_primary_monitor(py: Python, display_name: String, test_only: bool, persist: bool) -> PyResult<PyObject> {
+    let this_monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+
+    if test_only {
+        return Ok(test_primary_monitor(&this_monitor)?.as_str().into_py(py));
+    }
+
+    set_primary_monitor(&this_monitor, persist, true).map_err(display_change_py_err)?;
+    Ok(true.into_py(py))
+}
+
+/// Like `set_primary_monitor`, but returns which monitors were repositioned and which were
+/// skipped. With `strict=False`, monitors whose settings can't be read (e.g. one detached
+/// mid-operation) are skipped instead of failing the change. Skipped monitors keep their old
+/// coordinates while every other monitor shifts, so the resulting layout can overlap or leave gaps,
+/// in which case Windows rejects it with `DisplaySettingsError`.
+#[pyfunction]
+#[pyo3(name = "set_primary_monitor_report", signature = (display_name, persist=true, strict=false))]
+fn py_set_primary_monitor_report<'py>(
+    py: Python<'py>,
+    display_name: String,
+    persist: bool,
+    strict: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let this_monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    let report = set_primary_monitor(&this_monitor, persist, strict).map_err(display_change_py_err)?;
+    let repositioned: Vec<String> = report.repositioned.iter().map(MonitorHandle::native_identifier).collect();
+    let skipped = report
+        .skipped
+        .iter()
+        .map(|(monitor, err)| {
+            let entry = PyDict::new_bound(py);
+            entry.set_item("name", monitor.name())?;
+            entry.set_item("handle", monitor.hmonitor())?;
+            entry.set_item("error", err.to_string())?;
+            Ok(entry)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("repositioned", repositioned)?;
+    dict.set_item("skipped", skipped)?;
+    Ok(dict)
+}
+
+/// With `test_only`, the status string of a `CDS_TEST` dry run; otherwise `True` once the mode is
+/// applied.
+#[pyfunction]
+#[pyo3(signature = (display_name, width, height, test_only=false, persist=true))]
+fn set_display_mode(
+    py: Python,
+    display_name: String,
+    width: u32,
+    height: u32,
+    test_only: bool,
+    persist: bool,
+) -> PyResult<PyObject> {
+    let monitor = monitor_by_name(&display_name)
+        .ok_or_else(|| monitor_not_found(&display_name))?;
+    if test_only {
+        return Ok(monitor.test_mode(width, height)?.as_str().into_py(py));
+    }
+    match monitor.set_mode(width, height, persist) {
+        Ok(()) => Ok(true.into_py(py)),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
+    }
+}
+
+/// Raises `ValueError` if the rate isn't supported and `DisplaySettingsError` if the driver
+/// rejects the change.
+#[pyfunction]
+#[pyo3(signature = (display_name, hz, persist=true))]
+fn set_refresh_rate(display_name: String, hz: u32, persist: bool) -> PyResult<()> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    match monitor.set_refresh_rate(hz, persist) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (display_name, x, y, persist=true))]
+fn set_monitor_position(display_name: String, x: i32, y: i32, persist: bool) -> PyResult<()> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    match monitor.set_position(x, y, persist) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
     }
+}
 
-    #[getter]
-    fn position(&self) -> (i32, i32) {
-        let position = self.monitor_handle.position();
-        let x_pos = position.x;
-        let y_pos = position.y;
-        (x_pos, y_pos)
+#[pyfunction]
+#[pyo3(signature = (display_name, degrees, persist=true))]
+fn set_orientation(display_name: String, degrees: u32, persist: bool) -> PyResult<()> {
+    let orientation = Orientation::from_degrees(degrees).ok_or_else(|| {
+        PyValueError::new_err(format!("orientation must be 0, 90, 180 or 270, not {}", degrees))
+    })?;
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    monitor.set_orientation(orientation, persist).map_err(display_change_py_err)
+}
+
+/// Raises `OSError` if the monitor doesn't support HDR.
+#[pyfunction]
+fn set_hdr(display_name: String, enabled: bool) -> PyResult<()> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    Ok(monitor.set_hdr(enabled)?)
+}
+
+#[pyfunction]
+fn set_color_temperature(display_name: String, kelvin: u32) -> PyResult<()> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    match monitor.set_color_temperature(kelvin) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[pyfunction]
+#[pyo3(name = "set_monitor_power")]
+fn py_set_monitor_power(state: &str) -> PyResult<()> {
+    let state: MonitorPower = state.parse().map_err(PyValueError::new_err)?;
+    Ok(set_monitor_power(state)?)
+}
+
+/// Raises `ValueError` when asked to disable the primary monitor.
+#[pyfunction]
+#[pyo3(name = "set_monitor_enabled")]
+fn py_set_monitor_enabled(display_name: String, enabled: bool) -> PyResult<()> {
+    match set_monitor_enabled(&display_name, enabled) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Err(monitor_not_found(&display_name)),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
     }
+}
+
+#[pyfunction]
+#[pyo3(name = "disable_monitor_safely")]
+fn py_disable_monitor_safely(monitor: &Monitor) -> PyResult<()> {
+    Ok(disable_monitor_safely(&monitor.monitor_handle)?)
+}
 
+
+
+/// Handle returned by the `watch_*` functions; the callback keeps firing until `stop` is called.
+#[pyclass(module = "wmutil")]
+struct Watcher {
+    watcher: Option<DisplayWatcher>,
+}
+
+#[pymethods]
+impl Watcher {
     #[getter]
-    fn scale_factor(&self) -> f64 {
-        self.monitor_handle.scale_factor()
+    fn running(&self) -> bool {
+        self.watcher.as_ref().is_some_and(DisplayWatcher::is_running)
+    }
+
+    pub fn stop(&mut self, py: Python) {
+        if let Some(mut watcher) = self.watcher.take() {
+            // The watcher thread may be waiting for the GIL to run the callback.
+            py.allow_threads(move || watcher.stop());
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(&mut self, py: Python, _exc_type: PyObject, _exc_value: PyObject, _traceback: PyObject) {
+        self.stop(py);
+    }
+}
+
+#[pyfunction]
+#[pyo3(name = "display_generation")]
+fn py_display_generation() -> u64 {
+    display_generation()
+}
+
+/// `on_dpi_change`, if given, is called with the monitor and its new scale factor when the user
+/// changes a monitor's scaling.
+#[pyfunction]
+#[pyo3(name = "watch_display_changes", signature = (callback, on_dpi_change=None))]
+fn py_watch_display_changes(callback: PyObject, on_dpi_change: Option<PyObject>) -> PyResult<Watcher> {
+    let on_display_change = move |handles: Vec<MonitorHandle>| {
+        Python::with_gil(|py| {
+            let monitors: Vec<Monitor> = handles.into_iter().map(Monitor::new).collect();
+            if let Err(err) = callback.call1(py, (monitors,)) {
+                err.print(py);
+            }
+        });
+    };
+    let watcher = match on_dpi_change {
+        None => watch_display_changes(on_display_change)?,
+        Some(on_dpi_change) => watch_display_and_dpi_changes(on_display_change, move |handle, dpi| {
+            Python::with_gil(|py| {
+                if let Err(err) = on_dpi_change.call1(py, (Monitor::new(handle), dpi_to_scale_factor(dpi))) {
+                    err.print(py);
+                }
+            });
+        })?,
+    };
+    Ok(Watcher { watcher: Some(watcher) })
+}
+
+/// A `queue.Queue` that receives the list of monitors each time the display configuration changes,
+/// for consumers that would rather poll (e.g. from an asyncio executor) than run a callback on the
+/// watcher thread. Closing puts `None` on the queue to wake any blocked consumer.
+#[pyclass(name = "DisplayChangeEvents", module = "wmutil")]
+struct PyDisplayChangeEvents {
+    watcher: Option<DisplayWatcher>,
+    queue: PyObject,
+}
+
+/// Turns the closing sentinel into `EOFError`, putting it back so every other consumer sees it too.
+fn display_change_event(py: Python, queue: &PyObject, event: PyObject) -> PyResult<PyObject> {
+    if event.is_none(py) {
+        queue.call_method1(py, "put", (py.None(),))?;
+        return Err(PyEOFError::new_err("the display change events were closed"));
     }
+    Ok(event)
+}
 
+#[pymethods]
+impl PyDisplayChangeEvents {
     #[getter]
-    fn refresh_rate_millihertz(&self) -> Option<u32> {
-        self.monitor_handle.refresh_rate_millihertz()
+    fn queue(&self, py: Python) -> PyObject {
+        self.queue.clone_ref(py)
     }
 
     #[getter]
-    fn handle(&self) -> isize {
-        self.monitor_handle.0 as isize
+    fn closed(&self) -> bool {
+        self.watcher.is_none()
     }
 
-    pub fn set_primary(&self) -> PyResult<()> {
-        set_primary_monitor(self.name());
-        Ok(())
+    /// Waits for the next event like `queue.Queue.get`, raising `queue.Empty` on timeout and
+    /// `EOFError` once the events are closed and the queued ones are used up.
+    #[pyo3(signature = (block=true, timeout=None))]
+    pub fn get(slf: PyRef<'_, Self>, block: bool, timeout: Option<f64>) -> PyResult<PyObject> {
+        let py = slf.py();
+        let queue = slf.queue.clone_ref(py);
+        // Release the borrow before blocking so `close` can be called from another thread.
+        drop(slf);
+        let event = queue.call_method1(py, "get", (block, timeout))?;
+        display_change_event(py, &queue, event)
     }
 
-    pub fn __hash__(&self) -> isize {
-        self.handle()
+    pub fn get_nowait(&self, py: Python) -> PyResult<PyObject> {
+        let event = self.queue.call_method0(py, "get_nowait")?;
+        display_change_event(py, &self.queue, event)
     }
 
-    pub fn __eq__(&self, other: &Self) -> bool {
-        if self.handle() == other.handle() {
-            true
-        } else {
-            false
+    pub fn empty(&self, py: Python) -> PyResult<bool> {
+        self.queue.call_method0(py, "empty")?.extract(py)
+    }
+
+    /// Stops watching. Events already queued stay available; after them `get` raises `EOFError`,
+    /// including in threads already blocked in it.
+    pub fn close(&mut self, py: Python) -> PyResult<()> {
+        if let Some(mut watcher) = self.watcher.take() {
+            // The watcher thread may be waiting for the GIL to queue an event.
+            py.allow_threads(move || watcher.stop());
+            self.queue.call_method1(py, "put", (py.None(),))?;
         }
+        Ok(())
     }
 
-    pub fn __repr__(&self) -> PyResult<String> {
-        Ok(format!("<wmutil.Monitor object; handle={}>", self.handle()))
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        py: Python,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> PyResult<()> {
+        self.close(py)
     }
 }
 
 #[pyfunction]
-fn get_primary_monitor() -> Monitor {
-    let handle = primary_monitor();
-    Monitor {
-        monitor_handle: handle
-    }
+fn display_change_events(py: Python) -> PyResult<PyDisplayChangeEvents> {
+    let queue: PyObject = py.import_bound("queue")?.getattr("Queue")?.call0()?.unbind();
+    let thread_queue = queue.clone_ref(py);
+    let watcher = watch_display_changes(move |handles| {
+        Python::with_gil(|py| {
+            let monitors: Vec<Monitor> = handles.into_iter().map(Monitor::new).collect();
+            if let Err(err) = thread_queue.call_method1(py, "put", (monitors,)) {
+                err.print(py);
+            }
+        });
+    })?;
+    Ok(PyDisplayChangeEvents { watcher: Some(watcher), queue })
 }
 
+/// Releases the GIL while waiting so other Python threads keep running. KeyboardInterrupt isn't
+/// raised until the wait ends, so pass a `timeout_ms` and loop to stay interruptible.
 #[pyfunction]
-fn get_window_monitor(hwnd: isize) -> Monitor {
-    let handle = current_monitor(hwnd.into());
-    Monitor {
-        monitor_handle: handle
-    }
+#[pyo3(name = "wait_for_display_change", signature = (timeout_ms=None))]
+fn py_wait_for_display_change(py: Python, timeout_ms: Option<u64>) -> PyResult<bool> {
+    let timeout = timeout_ms.map(Duration::from_millis);
+    Ok(py.allow_threads(|| wait_for_display_change(timeout))?)
 }
 
 #[pyfunction]
-fn enumerate_monitors() -> Vec<Monitor> {
-    let mut monitors: Vec<Monitor> = Vec::new();
-    for monitor in available_monitors() {
-        monitors.push(Monitor { monitor_handle: monitor })
-    }
-    monitors
+#[pyo3(name = "watch_dpi_changes")]
+fn py_watch_dpi_changes(callback: PyObject) -> PyResult<Watcher> {
+    let watcher = watch_dpi_changes(move |handle, dpi| {
+        Python::with_gil(|py| {
+            if let Err(err) = callback.call1(py, (Monitor::new(handle), dpi)) {
+                err.print(py);
+            }
+        });
+    })?;
+    Ok(Watcher { watcher: Some(watcher) })
+}
+
+
+/// The dict returned by `Monitor.to_dict` and `snapshot_all_monitors`.
+fn monitor_info_to_dict<'py>(py: Python<'py>, info: &MonitorInfo) -> PyResult<Bound<'py, PyDict>> {
+    let (work_position, work_size) = info.work_area;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("name", &info.name)?;
+    dict.set_item("handle", info.monitor.hmonitor())?;
+    dict.set_item("position", [info.position.x, info.position.y])?;
+    dict.set_item("size", [info.size.width, info.size.height])?;
+    let work_area = [
+        PyList::new_bound(py, [work_position.x, work_position.y]),
+        PyList::new_bound(py, [work_size.width, work_size.height]),
+    ];
+    dict.set_item("work_area", PyList::new_bound(py, work_area))?;
+    dict.set_item("scale_factor", info.scale_factor)?;
+    dict.set_item("refresh_rate_millihertz", info.refresh_rate_millihertz)?;
+    dict.set_item("is_primary", info.is_primary())?;
+    Ok(dict)
 }
 
 #[pyfunction]
-fn get_monitor_from_point(x: i32, y: i32) -> Monitor {
-    let point = POINT {x, y};
-    let hmonitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTOPRIMARY) };
-    let handle = MonitorHandle::new(hmonitor);
-    Monitor {
-        monitor_handle: handle
+#[pyo3(name = "snapshot_all_monitors")]
+fn py_snapshot_all_monitors(py: Python) -> PyResult<Vec<Bound<PyDict>>> {
+    snapshot_all_monitors().iter().map(|info| monitor_info_to_dict(py, info)).collect()
+}
+
+/// Converts a `MonitorConfig` to and from the dicts exposed by `DisplayConfig.monitors`.
+fn monitor_config_to_dict<'py>(py: Python<'py>, monitor: &MonitorConfig) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("device_name", &monitor.device_name)?;
+    dict.set_item("position", (monitor.position.x, monitor.position.y))?;
+    dict.set_item("size", (monitor.size.width, monitor.size.height))?;
+    dict.set_item("refresh_rate", monitor.refresh_rate)?;
+    dict.set_item("bits_per_pixel", monitor.bits_per_pixel)?;
+    dict.set_item("orientation", monitor.orientation * 90)?;
+    dict.set_item("primary", monitor.primary)?;
+    Ok(dict)
+}
+
+fn monitor_config_from_dict(dict: &Bound<'_, PyDict>) -> PyResult<MonitorConfig> {
+    fn item<'py, T: FromPyObject<'py>>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<T> {
+        dict.get_item(key)?
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))?
+            .extract()
     }
+    let (x, y): (i32, i32) = item(dict, "position")?;
+    let (width, height): (u32, u32) = item(dict, "size")?;
+    let degrees: u32 = item(dict, "orientation")?;
+    if !matches!(degrees, 0 | 90 | 180 | 270) {
+        return Err(PyValueError::new_err(format!("orientation must be 0, 90, 180 or 270, not {}", degrees)));
+    }
+    Ok(MonitorConfig {
+        device_name: item(dict, "device_name")?,
+        position: PhysicalPosition { x, y },
+        size: PhysicalSize { width, height },
+        refresh_rate: item(dict, "refresh_rate")?,
+        bits_per_pixel: item(dict, "bits_per_pixel")?,
+        orientation: degrees / 90,
+        primary: item(dict, "primary")?,
+    })
 }
 
-fn wide_string(s: &str) -> Vec<u16> {
-    let mut vec: Vec<u16> = s.encode_utf16().collect();
-    vec.push(0);
-    vec
+#[pyclass(name = "DisplayConfig", module = "wmutil")]
+#[derive(Clone)]
+struct PyDisplayConfig {
+    config: DisplayConfig,
 }
 
+#[pymethods]
+impl PyDisplayConfig {
+    #[new]
+    fn new(monitors: Vec<Bound<'_, PyDict>>) -> PyResult<Self> {
+        let monitors = monitors.iter().map(monitor_config_from_dict).collect::<PyResult<_>>()?;
+        Ok(PyDisplayConfig { config: DisplayConfig { monitors } })
+    }
+
+    #[staticmethod]
+    fn capture() -> PyResult<Self> {
+        Ok(PyDisplayConfig { config: DisplayConfig::capture()? })
+    }
 
-fn get_dev_mode(display_name: &str) -> Result<DEVMODEW, String> {
-    let mut devmode: DEVMODEW = unsafe { std::mem::zeroed() };
-    devmode.dmSize = size_of::<DEVMODEW>() as u16;
+    #[getter]
+    fn monitors<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.config.monitors.iter().map(|monitor| monitor_config_to_dict(py, monitor)).collect()
+    }
 
-    let wide_name = wide_string(display_name);
+    pub fn apply(&self) -> PyResult<()> {
+        self.config.apply().map_err(display_change_py_err)
+    }
 
-    let success = unsafe {
-        EnumDisplaySettingsW(wide_name.as_ptr(), ENUM_CURRENT_SETTINGS, &mut devmode)
-    };
+    /// Pickles as the list of monitor dicts, so a saved layout can be written to disk and
+    /// restored in a later session.
+    pub fn __reduce__(&self, py: Python) -> PyResult<(PyObject, PyObject)> {
+        let monitors = self.monitors(py)?;
+        Ok((py.get_type_bound::<Self>().into_py(py), (monitors,).into_py(py)))
+    }
 
-    if success == 0 {
-        return Err(format!("Failed to retrieve settings for display: {}", display_name));
+    pub fn __eq__(&self, other: &Self) -> bool {
+        self.config == other.config
     }
 
-    Ok(devmode)
+    pub fn __repr__(&self) -> String {
+        format!("<wmutil.DisplayConfig object; monitors={}>", self.config.monitors.len())
+    }
 }
 
+/// Same as `DisplayConfig.capture()`.
+#[pyfunction]
+fn save_display_config() -> PyResult<PyDisplayConfig> {
+    PyDisplayConfig::capture()
+}
 
+/// Same as `config.apply()`: every monitor's settings are staged with `CDS_NORESET` and then
+/// applied together.
 #[pyfunction]
-fn set_primary_monitor(display_name: String) -> PyResult<bool> {
-    let all_monitors = enumerate_monitors();
-    let mut maybe_this_monitor: Option<Monitor> = None;
-    for monitor in all_monitors.clone() {
-        if monitor.name() == display_name {
-            maybe_this_monitor = Some(monitor);
-            break
-        }
+fn restore_display_config(config: &PyDisplayConfig) -> PyResult<()> {
+    config.apply()
+}
+
+#[pyclass(name = "DisplayChangeMonitor", module = "wmutil")]
+struct PyDisplayChangeMonitor {
+    monitor: DisplayChangeMonitor,
+}
+
+#[pymethods]
+impl PyDisplayChangeMonitor {
+    #[new]
+    fn new() -> Self {
+        PyDisplayChangeMonitor { monitor: DisplayChangeMonitor::new() }
+    }
+
+    pub fn poll(&mut self) -> bool {
+        self.monitor.poll()
+    }
+}
+
+/// A context manager that queues display changes and applies them in one commit on exit, or
+/// discards them if the block raised.
+#[pyclass(name = "DisplayTransaction", module = "wmutil")]
+struct PyDisplayTransaction {
+    transaction: Option<DisplayTransaction>,
+    result: Option<DisplayChangeStatus>,
+}
+
+impl PyDisplayTransaction {
+    fn pending(&mut self) -> PyResult<&mut DisplayTransaction> {
+        self.transaction
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("the transaction has already been committed or discarded"))
+    }
+}
+
+#[pymethods]
+impl PyDisplayTransaction {
+    #[new]
+    fn new() -> Self {
+        PyDisplayTransaction { transaction: Some(DisplayTransaction::new()), result: None }
     }
 
-    // todo: raise a proper exception instead of a panic exception
-    assert!(maybe_this_monitor.is_some(), "Monitor with name {:?} not found", display_name);
+    pub fn set_mode(&mut self, display_name: &str, width: u32, height: u32) -> PyResult<()> {
+        let monitor = monitor_by_name(display_name).ok_or_else(|| monitor_not_found(display_name))?;
+        self.pending()?
+            .set_mode(&monitor, width, height)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    pub fn set_refresh_rate(&mut self, display_name: &str, hz: u32) -> PyResult<()> {
+        let monitor = monitor_by_name(display_name).ok_or_else(|| monitor_not_found(display_name))?;
+        self.pending()?.set_refresh_rate(&monitor, hz);
+        Ok(())
+    }
+
+    pub fn set_position(&mut self, display_name: &str, x: i32, y: i32) -> PyResult<()> {
+        let monitor = monitor_by_name(display_name).ok_or_else(|| monitor_not_found(display_name))?;
+        self.pending()?
+            .set_position(&monitor, x, y)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    pub fn set_primary(&mut self, display_name: &str) -> PyResult<()> {
+        let monitor = monitor_by_name(display_name).ok_or_else(|| monitor_not_found(display_name))?;
+        self.pending()?
+            .set_primary(&monitor)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
 
-    let this_monitor = maybe_this_monitor.unwrap();
+    /// Applies the queued changes and returns the status string of the commit.
+    pub fn commit(&mut self) -> PyResult<&'static str> {
+        let transaction = self.pending()?;
+        let status = mem::take(transaction).commit()?;
+        self.transaction = None;
+        self.result = Some(status);
+        Ok(status.as_str())
+    }
 
-    let (this_x, this_y) = this_monitor.position();
+    pub fn discard(&mut self) {
+        self.transaction = None;
+    }
 
-    if (this_x == 0 && this_y == 0) {
-        // the requested monitor is already the primary monitor
-        return Ok(true)
+    /// The status string of the commit, or `None` if it hasn't been committed.
+    #[getter]
+    fn result(&self) -> Option<&'static str> {
+        self.result.map(DisplayChangeStatus::as_str)
     }
 
-    let x_offset = this_x.neg();
-    let y_offset = this_y.neg();
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
 
-    let display_name_string = display_name.as_str();
-    let wide_name = wide_string(display_name_string);
+    fn __exit__(&mut self, exc_type: PyObject, _exc_value: PyObject, _traceback: PyObject, py: Python) -> PyResult<()> {
+        if !exc_type.is_none(py) || self.transaction.is_none() {
+            self.discard();
+            return Ok(());
+        }
+        self.commit().map(|_| ())
+    }
+}
 
-    for monitor in all_monitors.clone() {
-        if monitor.name() != display_name {
-            let mut devmode: DEVMODEW = get_dev_mode(monitor.name().as_str()).unwrap();
-            unsafe {
-                let (monitor_x, monitor_y) = monitor.position();
-                let new_x = monitor_x + x_offset;
-                let new_y = monitor_y + y_offset;
-                devmode.Anonymous1.Anonymous2.dmPosition = POINTL { x: new_x, y: new_y };
-                // println!("display: {} old: {} {} new: {} {}", monitor.name(), monitor_x, monitor_y, new_x, new_y);
-                ChangeDisplaySettingsExW(wide_string(monitor.name().as_str()).as_ptr(), &mut devmode, 0, CDS_UPDATEREGISTRY | CDS_NORESET, null_mut());
+#[pyfunction]
+#[pyo3(name = "apply_with_confirmation")]
+fn py_apply_with_confirmation(py: Python, config: &PyDisplayConfig, timeout_secs: f64, confirm: PyObject) -> PyResult<bool> {
+    if !(timeout_secs >= 0.0 && timeout_secs.is_finite()) {
+        return Err(PyValueError::new_err("timeout_secs must be a non-negative number"));
+    }
+    let mut confirm_error = None;
+    let kept = apply_with_confirmation(&config.config, Duration::from_secs_f64(timeout_secs), || {
+        match confirm.call0(py).and_then(|result| result.bind(py).is_truthy()) {
+            Ok(confirmed) => confirmed,
+            Err(err) => {
+                confirm_error = Some(err);
+                false
             }
         }
+    })?;
+    match confirm_error {
+        Some(err) => Err(err),
+        None => Ok(kept),
     }
-    let mut devmode: DEVMODEW = get_dev_mode(display_name_string).unwrap();
-    unsafe {
-        // println!("{} being set as primary to 0 0", display_name);
-        devmode.Anonymous1.Anonymous2.dmPosition = POINTL { x: 0, y: 0 };
-        ChangeDisplaySettingsExW(wide_name.as_ptr(), &mut devmode, 0, CDS_SET_PRIMARY | CDS_UPDATEREGISTRY | CDS_NORESET, null_mut());
+}
+
+/// A display change that is undone when its timeout elapses unless `confirm` is called first.
+#[pyclass(name = "PendingRevert", module = "wmutil")]
+struct PyPendingRevert {
+    pending: PendingRevert,
+}
+
+#[pymethods]
+impl PyPendingRevert {
+    #[getter]
+    fn pending(&self) -> bool {
+        self.pending.is_pending()
     }
 
-    let result = unsafe {
-        ChangeDisplaySettingsExW(null_mut(), null_mut(), 0, 0, null_mut())
-    };
-    if result == DISP_CHANGE_SUCCESSFUL {
-        Ok(true)
-    } else {
-        Ok(false)
+    /// Returns `False` if the change was already reverted.
+    pub fn confirm(&self) -> bool {
+        self.pending.confirm()
+    }
+
+    pub fn revert(&self) -> PyResult<()> {
+        self.pending.revert().map_err(display_change_py_err)
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (display_name, width, height, revert_after_ms=15000))]
+fn set_display_mode_with_confirm(
+    display_name: String,
+    width: u32,
+    height: u32,
+    revert_after_ms: u64,
+) -> PyResult<PyPendingRevert> {
+    let monitor = monitor_by_name(&display_name).ok_or_else(|| monitor_not_found(&display_name))?;
+    match set_mode_with_confirmation(&monitor, width, height, Duration::from_millis(revert_after_ms)) {
+        Ok(pending) => Ok(PyPendingRevert { pending }),
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => Err(PyValueError::new_err(err.to_string())),
+        Err(err) => Err(display_change_py_err(err)),
     }
 }
 
 
+#[pyfunction]
+#[pyo3(name = "set_taskbar_monitor")]
+fn py_set_taskbar_monitor(monitor: &Monitor, edge: &str) -> PyResult<()> {
+    let edge: ScreenEdge = edge.parse().map_err(PyValueError::new_err)?;
+    Ok(set_taskbar_monitor(&monitor.monitor_handle, edge)?)
+}
+
 
 #[pymodule]
-fn wmutil(_py: Python, m: &PyModule) -> PyResult<()> {
+fn wmutil(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("MonitorNotFoundError", py.get_type_bound::<MonitorNotFoundError>())?;
+    m.add("InvalidMonitorHandleError", py.get_type_bound::<InvalidMonitorHandleError>())?;
+    m.add("BrightnessNotSupportedError", py.get_type_bound::<BrightnessNotSupportedError>())?;
+    m.add("DisplaySettingsError", py.get_type_bound::<DisplaySettingsError>())?;
+    m.add("MONITORINFOF_PRIMARY", MONITORINFOF_PRIMARY)?;
     m.add_class::<Monitor>()?;
-    m.add_function(wrap_pyfunction!(enumerate_monitors, m)?);
-    m.add_function(wrap_pyfunction!(get_window_monitor, m)?);
-    m.add_function(wrap_pyfunction!(get_primary_monitor, m)?);
-    m.add_function(wrap_pyfunction!(get_monitor_from_point, m)?);
-    m.add_function(wrap_pyfunction!(set_primary_monitor, m)?);
+    m.add_class::<PyDisplayMode>()?;
+    m.add_function(wrap_pyfunction!(enumerate_monitors, m)?)?;
+    m.add_function(wrap_pyfunction!(py_snapshot_all_monitors, m)?)?;
+    m.add_function(wrap_pyfunction!(get_window_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(get_primary_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(get_monitor_from_point, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_from_rect, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_primary_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_primary_monitor_report, m)?)?;
+    m.add_function(wrap_pyfunction!(py_disable_monitor_safely, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_monitor_enabled, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_monitor_power, m)?)?;
+    m.add_function(wrap_pyfunction!(set_color_temperature, m)?)?;
+    m.add_function(wrap_pyfunction!(set_hdr, m)?)?;
+    m.add_function(wrap_pyfunction!(set_display_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(set_orientation, m)?)?;
+    m.add_function(wrap_pyfunction!(set_monitor_position, m)?)?;
+    m.add_function(wrap_pyfunction!(set_refresh_rate, m)?)?;
+    m.add_function(wrap_pyfunction!(get_monitor_from_cursor, m)?)?;
+    m.add_function(wrap_pyfunction!(get_monitor_by_name, m)?)?;
+    m.add_function(wrap_pyfunction!(get_monitor_by_number, m)?)?;
+    m.add_function(wrap_pyfunction!(py_center_rect, m)?)?;
+    m.add_function(wrap_pyfunction!(get_display_topology, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_display_topology, m)?)?;
+    m.add_function(wrap_pyfunction!(get_monitor_from_handle, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_for_points, m)?)?;
+    m.add_function(wrap_pyfunction!(get_layout_diagram, m)?)?;
+    m.add_function(wrap_pyfunction!(py_desktop_is_rectangular, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_in_direction, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_adjacency, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_rows, m)?)?;
+    m.add_function(wrap_pyfunction!(py_monitor_at_virtual_pixel, m)?)?;
+    m.add_function(wrap_pyfunction!(get_virtual_screen_bounds, m)?)?;
+    m.add_function(wrap_pyfunction!(py_maximized_bounds_for_window, m)?)?;
+    m.add_function(wrap_pyfunction!(py_suggest_primary_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_largest_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_highest_resolution_monitor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_refresh_rate_groups, m)?)?;
+    m.add_function(wrap_pyfunction!(py_refresh_rates_uniform, m)?)?;
+    m.add_function(wrap_pyfunction!(py_is_remote_session, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_all_scale_percent, m)?)?;
+    m.add_function(wrap_pyfunction!(set_scale_factor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_all_resolution, m)?)?;
+    m.add_function(wrap_pyfunction!(py_arrange_horizontally, m)?)?;
+    m.add_function(wrap_pyfunction!(py_dpi_diagnostics, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_process_dpi_awareness, m)?)?;
+    m.add_class::<PyDpiAwarenessContext>()?;
+    m.add_function(wrap_pyfunction!(dpi_awareness, m)?)?;
+    m.add_function(wrap_pyfunction!(py_desktop_pixel_format, m)?)?;
+    m.add_class::<Watcher>()?;
+    m.add_function(wrap_pyfunction!(py_watch_dpi_changes, m)?)?;
+    m.add_function(wrap_pyfunction!(py_watch_display_changes, m)?)?;
+    m.add_function(wrap_pyfunction!(py_wait_for_display_change, m)?)?;
+    m.add_class::<PyDisplayChangeEvents>()?;
+    m.add_function(wrap_pyfunction!(display_change_events, m)?)?;
+    m.add_function(wrap_pyfunction!(py_display_generation, m)?)?;
+    m.add_class::<PyDisplayConfig>()?;
+    m.add_function(wrap_pyfunction!(save_display_config, m)?)?;
+    m.add_function(wrap_pyfunction!(restore_display_config, m)?)?;
+    m.add_class::<PyDisplayChangeMonitor>()?;
+    m.add_class::<PyDisplayTransaction>()?;
+    m.add_function(wrap_pyfunction!(py_apply_with_confirmation, m)?)?;
+    m.add_class::<PyPendingRevert>()?;
+    m.add_function(wrap_pyfunction!(set_display_mode_with_confirm, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_taskbar_monitor, m)?)?;
 
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primary_layout_stays_contiguous_with_mixed_dpi() {
+        // A 4K monitor at 150% as the current primary with a 1080p monitor at 100% to its right.
+        // dmPosition is physical, so the 1080p monitor starts at x=3840 whatever the scaling.
+        let monitors = [
+            (PhysicalPosition { x: 0, y: 0 }, PhysicalSize { width: 3840, height: 2160 }, false),
+            (PhysicalPosition { x: 3840, y: 0 }, PhysicalSize { width: 1920, height: 1080 }, true),
+        ];
+        let rects = primary_layout_rects(&monitors).unwrap();
+        assert_eq!((rects[1].left, rects[1].top), (0, 0));
+        assert_eq!((rects[0].left, rects[0].top, rects[0].right), (-3840, 0, 0));
+        assert!(layout_is_contiguous(&rects));
+    }
+
+    #[test]
+    fn primary_layout_needs_a_primary() {
+        let monitors = [(PhysicalPosition { x: 0, y: 0 }, PhysicalSize { width: 1920, height: 1080 }, false)];
+        assert!(primary_layout_rects(&monitors).is_none());
+    }
+
+    fn rect(left: i32, top: i32, width: i32, height: i32) -> RECT {
+        RECT { left, top, right: left + width, bottom: top + height }
+    }
+
+    #[test]
+    fn rects_share_edge_needs_some_length_of_edge() {
+        let a = rect(0, 0, 1920, 1080);
+        assert!(rects_share_edge(&a, &rect(1920, 0, 1920, 1080)));
+        assert!(rects_share_edge(&a, &rect(0, 1080, 1280, 720)));
+        assert!(rects_share_edge(&a, &rect(1920, 1079, 1280, 720)));
+        // Touching only at the corner.
+        assert!(!rects_share_edge(&a, &rect(1920, 1080, 1920, 1080)));
+        // A 1px gap.
+        assert!(!rects_share_edge(&a, &rect(1921, 0, 1920, 1080)));
+    }
+
+    #[test]
+    fn adjacent_rect_side_by_side_and_stacked() {
+        let side_by_side = [rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080)];
+        assert_eq!(adjacent_rect(&side_by_side[0], Direction::Right, &side_by_side), Some(1));
+        assert_eq!(adjacent_rect(&side_by_side[1], Direction::Left, &side_by_side), Some(0));
+        assert_eq!(adjacent_rect(&side_by_side[0], Direction::Left, &side_by_side), None);
+        assert_eq!(adjacent_rect(&side_by_side[0], Direction::Down, &side_by_side), None);
+
+        let stacked = [rect(0, 0, 1920, 1080), rect(0, 1080, 1920, 1080)];
+        assert_eq!(adjacent_rect(&stacked[0], Direction::Down, &stacked), Some(1));
+        assert_eq!(adjacent_rect(&stacked[1], Direction::Up, &stacked), Some(0));
+        assert_eq!(adjacent_rect(&stacked[0], Direction::Right, &stacked), None);
+    }
+
+    #[test]
+    fn adjacent_rect_l_shaped() {
+        let rects = [rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080), rect(0, 1080, 1920, 1080)];
+        assert_eq!(adjacent_rect(&rects[0], Direction::Down, &rects), Some(2));
+        // The bottom monitor and the right one only meet at a corner.
+        assert_eq!(adjacent_rect(&rects[2], Direction::Right, &rects), None);
+        assert_eq!(adjacent_rect(&rects[1], Direction::Down, &rects), None);
+    }
+
+    #[test]
+    fn adjacent_rect_mixed_sizes_prefers_nearest_then_larger_overlap() {
+        let rects = [
+            rect(0, 0, 3840, 2160),
+            rect(3840, 500, 1920, 1080),
+            rect(3840, 1580, 1920, 1080),
+            rect(-2000, 0, 1920, 1080),
+        ];
+        assert_eq!(adjacent_rect(&rects[0], Direction::Right, &rects), Some(1));
+        assert_eq!(adjacent_rect(&rects[2], Direction::Left, &rects), Some(0));
+        assert_eq!(adjacent_rect(&rects[1], Direction::Down, &rects), Some(2));
+        // Monitors don't have to touch.
+        assert_eq!(adjacent_rect(&rects[0], Direction::Left, &rects), Some(3));
+    }
+
+    #[test]
+    fn rect_rows_groups_vertically_overlapping_rects() {
+        let side_by_side = [rect(1920, 0, 1920, 1080), rect(0, 0, 1920, 1080)];
+        assert_eq!(rect_rows(&side_by_side), [vec![1, 0]]);
+
+        let stacked = [rect(0, 1080, 1920, 1080), rect(0, 0, 1920, 1080)];
+        assert_eq!(rect_rows(&stacked), [vec![1], vec![0]]);
+
+        let l_shaped = [rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080), rect(0, 1080, 1920, 1080)];
+        assert_eq!(rect_rows(&l_shaped), [vec![0, 1], vec![2]]);
+
+        // The 4K monitor spans both 1080p monitors stacked to its right, so all three share a row.
+        let mixed = [rect(3840, 1080, 1920, 1080), rect(0, 0, 3840, 2160), rect(3840, 0, 1920, 1080)];
+        assert_eq!(rect_rows(&mixed), [vec![1, 2, 0]]);
+        assert!(rect_rows(&[]).is_empty());
+    }
+
+    #[test]
+    fn rects_tile_bounding_box_detects_gaps() {
+        assert!(rects_tile_bounding_box(&[]));
+        assert!(rects_tile_bounding_box(&[rect(0, 0, 1920, 1080)]));
+        assert!(rects_tile_bounding_box(&[rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080)]));
+        assert!(rects_tile_bounding_box(&[rect(0, 0, 1920, 1080), rect(0, 1080, 1920, 1080)]));
+        assert!(rects_tile_bounding_box(&[
+            rect(0, 0, 3840, 2160),
+            rect(3840, 0, 1920, 1080),
+            rect(3840, 1080, 1920, 1080),
+        ]));
+        // An L shape leaves the bottom-right corner uncovered.
+        assert!(!rects_tile_bounding_box(&[
+            rect(0, 0, 1920, 1080),
+            rect(1920, 0, 1920, 1080),
+            rect(0, 1080, 1920, 1080),
+        ]));
+        // Mixed heights, top-aligned.
+        assert!(!rects_tile_bounding_box(&[rect(0, 0, 3840, 2160), rect(3840, 0, 1920, 1080)]));
+    }
+
+    fn diagram(rects: &[RECT]) -> Vec<String> {
+        let monitors: Vec<(RECT, String)> = rects
+            .iter()
+            .enumerate()
+            .map(|(index, rect)| (*rect, format!(r"\\.\DISPLAY{}", index + 1)))
+            .collect();
+        render_layout_diagram(&monitors).lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn layout_diagram_side_by_side_and_stacked() {
+        let lines = diagram(&[rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080)]);
+        assert!(lines[0].starts_with('┌') && lines[0].contains("┐┌") && lines[0].ends_with('┐'));
+        assert!(lines[1].contains("│0: DISPLAY1") && lines[1].contains("││1: DISPLAY2"));
+        assert!(lines.last().unwrap().contains("┘└"));
+        assert_eq!(lines[0].chars().count(), DIAGRAM_COLUMNS as usize - 1);
+
+        let lines = diagram(&[rect(0, 0, 1920, 1080), rect(0, 1080, 1920, 1080)]);
+        let second_top = lines.iter().position(|line| line.starts_with('└')).unwrap() + 1;
+        assert!(lines[second_top].starts_with('┌'));
+        assert!(lines[second_top + 1].starts_with("│1: DISPLAY2"));
+        assert!(lines.iter().all(|line| !line.contains("┐┌")));
+    }
+
+    #[test]
+    fn layout_diagram_l_shaped_leaves_the_gap_blank() {
+        let lines = diagram(&[rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080), rect(0, 1080, 1920, 1080)]);
+        let bottom_box = lines.iter().rposition(|line| line.starts_with('┌')).unwrap();
+        assert!(lines[bottom_box + 1].starts_with("│2: DISPLAY3"));
+        // Nothing is drawn to the right of the bottom monitor.
+        assert!(lines[bottom_box..].iter().all(|line| line.chars().count() < DIAGRAM_COLUMNS as usize / 2 + 1));
+    }
+
+    #[test]
+    fn layout_diagram_mixed_sizes_and_empty() {
+        let lines = diagram(&[rect(0, 0, 3840, 2160), rect(3840, 0, 1920, 1080)]);
+        // The 1080p box ends halfway down the 4K one, which carries on alone below it.
+        let small_bottom = lines.iter().position(|line| line.ends_with('┘')).unwrap();
+        assert_eq!(small_bottom + 1, lines.len() / 2);
+        assert!(lines[small_bottom + 1..lines.len() - 1].iter().all(|line| line.ends_with('│')));
+        assert!(lines.last().unwrap().starts_with('└'));
+        assert_eq!(render_layout_diagram(&[]), "");
+    }
+
+    #[test]
+    fn layout_is_contiguous_accepts_connected_layouts() {
+        assert!(layout_is_contiguous(&[]));
+        assert!(layout_is_contiguous(&[rect(0, 0, 1920, 1080)]));
+        assert!(layout_is_contiguous(&[
+            rect(0, 0, 1920, 1080),
+            rect(1920, 0, 1920, 1080),
+            rect(0, 1080, 1920, 1080),
+        ]));
+    }
+
+    #[test]
+    fn layout_is_contiguous_rejects_gaps_islands_and_overlaps() {
+        assert!(!layout_is_contiguous(&[rect(0, 0, 1920, 1080), rect(1920, 1080, 1920, 1080)]));
+        assert!(!layout_is_contiguous(&[rect(0, 0, 1920, 1080), rect(1921, 0, 1920, 1080)]));
+        // Two connected monitors and an island that touches neither.
+        assert!(!layout_is_contiguous(&[
+            rect(0, 0, 1920, 1080),
+            rect(1920, 0, 1920, 1080),
+            rect(5000, 0, 1920, 1080),
+        ]));
+        assert!(!layout_is_contiguous(&[rect(0, 0, 1920, 1080), rect(1000, 0, 1920, 1080)]));
+    }
+
+    #[test]
+    fn wide_device_name_round_trips_non_ascii_names() {
+        use std::os::windows::ffi::OsStrExt;
+
+        // "\\.\ДИСПЛЕЙ" followed by an unpaired high surrogate, which no `String` can hold.
+        let mut name: Vec<u16> = r"\\.\ДИСПЛЕЙ".encode_utf16().collect();
+        name.push(0xD800);
+        name.push('1' as u16);
+        let mut monitor_info: MONITORINFOEXW = unsafe { mem::zeroed() };
+        monitor_info.szDevice[..name.len()].copy_from_slice(&name);
+        // Leftovers past the NUL must not end up in the name.
+        monitor_info.szDevice[name.len() + 1] = 'X' as u16;
+
+        let wide_name = wide_device_name(&monitor_info);
+        let mut expected = name.clone();
+        expected.push(0);
+        assert_eq!(wide_name, expected);
+        assert_eq!(decode_wide(&wide_name).encode_wide().collect::<Vec<_>>(), name);
+    }
+
+    #[test]
+    fn color_temperature_ramp_is_identity_at_6500k() {
+        assert_eq!(color_temperature_ramp(6500), linear_gamma_ramp());
+    }
+
+    #[test]
+    fn blackbody_rgb_clamps_at_the_extremes() {
+        for kelvin in [0, 1, *COLOR_TEMPERATURE_RANGE.start(), *COLOR_TEMPERATURE_RANGE.end(), 40000, u32::MAX] {
+            for channel in blackbody_rgb(kelvin) {
+                assert!((0.0..=255.0).contains(&channel), "{}K gave {}", kelvin, channel);
+            }
+        }
+        assert_eq!(blackbody_rgb(1000)[2], 0.0);
+        assert_eq!(blackbody_rgb(10000)[2], 255.0);
+    }
+
+    #[test]
+    fn color_temperature_ramps_are_monotonic_and_never_brighten() {
+        let linear = linear_gamma_ramp();
+        for kelvin in (1000..=10000).step_by(500) {
+            let ramp = color_temperature_ramp(kelvin);
+            for (channel, linear_channel) in ramp.iter().zip(&linear) {
+                assert!(channel.windows(2).all(|pair| pair[0] <= pair[1]), "{}K is not monotonic", kelvin);
+                assert!(channel.iter().zip(linear_channel).all(|(value, linear)| value <= linear));
+            }
+        }
+        let warm = color_temperature_ramp(3000);
+        assert!(warm[2][255] < warm[1][255] && warm[1][255] < warm[0][255]);
+    }
+
+    /// A base EDID block modeled on a Dell U2415: serial number and name descriptors padded the
+    /// way monitors pad them, and a valid checksum.
+    const DELL_EDID: [u8; 128] = [
+        0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x10, 0xAC, 0xC4, 0xA0, 0x4C, 0x4B, 0x4C, 0x4C,
+        0x1E, 0x18, 0x01, 0x04, 0xA5, 0x34, 0x20, 0x78, 0x3A, 0xEE, 0x95, 0xA3, 0x54, 0x4C, 0x99, 0x26,
+        0x0F, 0x50, 0x54, 0xA5, 0x4B, 0x00, 0x71, 0x4F, 0x81, 0x80, 0xA9, 0x40, 0xD1, 0xC0, 0xD1, 0x00,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x28, 0x3C, 0x80, 0xA0, 0x70, 0xB0, 0x23, 0x40, 0x30, 0x20,
+        0x36, 0x00, 0x06, 0x44, 0x21, 0x00, 0x00, 0x1A, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x37, 0x4D, 0x54,
+        0x30, 0x31, 0x38, 0x34, 0x49, 0x30, 0x4C, 0x41, 0x4C, 0x0A, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x44,
+        0x45, 0x4C, 0x4C, 0x20, 0x55, 0x32, 0x34, 0x31, 0x35, 0x0A, 0x20, 0x20, 0x00, 0x00, 0x00, 0xFD,
+        0x00, 0x38, 0x4C, 0x1E, 0x51, 0x11, 0x00, 0x0A, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x12,
+    ];
+
+    #[test]
+    fn edid_header_fields() {
+        assert_eq!(DELL_EDID.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)), 0);
+        assert_eq!(edid_manufacturer_id(&DELL_EDID).as_deref(), Some("DEL"));
+        assert_eq!(edid_product_code(&DELL_EDID), Some(0xA0C4));
+        assert_eq!(edid_header_serial(&DELL_EDID), Some(0x4C4C_4B4C));
+        assert_eq!(edid_product_id(&DELL_EDID).as_deref(), Some("10ACC4A04C4B4C4C"));
+    }
+
+    #[test]
+    fn edid_header_fields_reject_bad_or_short_data() {
+        let mut bad_header = DELL_EDID;
+        bad_header[0] = 0x01;
+        for edid in [&bad_header[..], &DELL_EDID[..15], &[]] {
+            assert_eq!(edid_manufacturer_id(edid), None);
+            assert_eq!(edid_product_code(edid), None);
+            assert_eq!(edid_header_serial(edid), None);
+        }
+        // The header alone is enough for the header fields.
+        assert_eq!(edid_product_code(&DELL_EDID[..16]), Some(0xA0C4));
+    }
+
+    #[test]
+    fn edid_header_serial_treats_zero_as_missing() {
+        let mut edid = DELL_EDID;
+        edid[12..16].fill(0);
+        assert_eq!(edid_header_serial(&edid), None);
+    }
+
+    #[test]
+    fn edid_manufacturer_id_rejects_letters_out_of_range() {
+        let mut edid = DELL_EDID;
+        // The first letter is 0, which doesn't map to a letter.
+        edid[8..10].copy_from_slice(&0x00ACu16.to_be_bytes());
+        assert_eq!(edid_manufacturer_id(&edid), None);
+    }
+
+    #[test]
+    fn edid_descriptor_string_strips_padding() {
+        assert_eq!(edid_descriptor_string(&DELL_EDID, EDID_SERIAL_NUMBER_TAG).as_deref(), Some("7MT0184I0LAL"));
+        // The name is terminated by 0x0A and padded with spaces.
+        assert_eq!(edid_descriptor_string(&DELL_EDID, 0xFC).as_deref(), Some("DELL U2415"));
+
+        let mut edid = DELL_EDID;
+        // Space padding without a 0x0A terminator.
+        edid[95..108].copy_from_slice(b"U2415        ");
+        assert_eq!(edid_descriptor_string(&edid, 0xFC).as_deref(), Some("U2415"));
+        // Nothing but padding.
+        edid[95..108].copy_from_slice(b"\n            ");
+        assert_eq!(edid_descriptor_string(&edid, 0xFC), None);
+    }
+
+    #[test]
+    fn edid_descriptor_string_without_the_descriptor() {
+        let mut edid = DELL_EDID;
+        edid[75] = 0xFE;
+        assert_eq!(edid_descriptor_string(&edid, EDID_SERIAL_NUMBER_TAG), None);
+        // A detailed timing descriptor whose fourth byte happens to match isn't a text descriptor.
+        assert_eq!(edid_descriptor_string(&DELL_EDID, DELL_EDID[57]), None);
+    }
+
+    #[test]
+    fn edid_descriptor_string_rejects_bad_or_truncated_data() {
+        let mut bad_header = DELL_EDID;
+        bad_header[7] = 0xFF;
+        assert_eq!(edid_descriptor_string(&bad_header, EDID_SERIAL_NUMBER_TAG), None);
+        assert_eq!(edid_descriptor_string(&DELL_EDID[..127], EDID_SERIAL_NUMBER_TAG), None);
+        assert_eq!(edid_descriptor_string(&DELL_EDID[..16], EDID_SERIAL_NUMBER_TAG), None);
+    }
+
+    #[test]
+    fn dev_mode_builder_setters_set_their_field_bits() {
+        let empty = DevModeBuilder::new().build();
+        assert_eq!(empty.dmSize as usize, size_of::<DEVMODEW>());
+        assert_eq!(empty.dmFields, 0);
+
+        let cases: [(fn(DevModeBuilder) -> DevModeBuilder, DEVMODE_FIELD_FLAGS); 6] = [
+            (|builder| builder.width(1920), DM_PELSWIDTH),
+            (|builder| builder.height(1080), DM_PELSHEIGHT),
+            (|builder| builder.refresh_rate(144), DM_DISPLAYFREQUENCY),
+            (|builder| builder.bits_per_pixel(32), DM_BITSPERPEL),
+            (|builder| builder.orientation(DMDO_90), DM_DISPLAYORIENTATION),
+            (|builder| builder.position(-1920, 0), DM_POSITION),
+        ];
+        for (set, field) in cases {
+            assert_eq!(set(DevModeBuilder::new()).build().dmFields, field);
+        }
+
+        let devmode = DevModeBuilder::new().width(1920).height(1080).position(-1920, 0).build();
+        assert_eq!((devmode.dmPelsWidth, devmode.dmPelsHeight), (1920, 1080));
+        let position = unsafe { devmode.Anonymous1.Anonymous2.dmPosition };
+        assert_eq!((position.x, position.y), (-1920, 0));
+        assert_eq!(devmode.dmFields, DM_PELSWIDTH | DM_PELSHEIGHT | DM_POSITION);
+    }
+
+    #[test]
+    fn dev_mode_builder_from_devmode_drops_driver_data() {
+        let mut original = DevModeBuilder::new().width(2560).build();
+        original.dmSize = 0;
+        original.dmDriverExtra = 64;
+        let devmode = DevModeBuilder::from_devmode(original).refresh_rate(60).build();
+        assert_eq!(devmode.dmSize as usize, size_of::<DEVMODEW>());
+        assert_eq!(devmode.dmDriverExtra, 0);
+        assert_eq!(devmode.dmPelsWidth, 2560);
+        assert_eq!(devmode.dmFields, DM_PELSWIDTH | DM_DISPLAYFREQUENCY);
+    }
+
+    fn mode(width: u32, height: u32, refresh_rate: u32) -> DisplayMode {
+        DisplayMode { width, height, refresh_rate, bits_per_pixel: 32 }
+    }
+
+    #[test]
+    fn collect_display_modes_stops_on_a_driver_that_cycles_forever() {
+        let cycle = [mode(1920, 1080, 60), mode(1280, 720, 60), mode(1920, 1080, 144)];
+        let mut calls = 0;
+        let modes = collect_display_modes(|mode_number| {
+            calls += 1;
+            Some(cycle[mode_number as usize % cycle.len()])
+        });
+        assert_eq!(modes, cycle);
+        assert_eq!(calls, cycle.len() as u32 + MAX_REPEATED_MODES);
+    }
+
+    #[test]
+    fn collect_display_modes_drops_duplicates_in_first_seen_order() {
+        let reported = [
+            mode(1920, 1080, 60),
+            mode(1280, 720, 60),
+            mode(1920, 1080, 60),
+            mode(2560, 1440, 60),
+            mode(1280, 720, 60),
+        ];
+        let modes = collect_display_modes(|mode_number| reported.get(mode_number as usize).copied());
+        assert_eq!(modes, [mode(1920, 1080, 60), mode(1280, 720, 60), mode(2560, 1440, 60)]);
+    }
+
+    #[test]
+    fn display_change_error_maps_every_disp_change_code() {
+        let table = [
+            (DISP_CHANGE_BADMODE, DisplayChangeError::BadMode, "DISP_CHANGE_BADMODE", "the graphics mode is not supported"),
+            (DISP_CHANGE_BADFLAGS, DisplayChangeError::BadFlags, "DISP_CHANGE_BADFLAGS", "an invalid set of flags was passed in"),
+            (
+                DISP_CHANGE_RESTART,
+                DisplayChangeError::Restart,
+                "DISP_CHANGE_RESTART",
+                "the computer must be restarted for the graphics mode to work",
+            ),
+            (
+                DISP_CHANGE_FAILED,
+                DisplayChangeError::Failed,
+                "DISP_CHANGE_FAILED",
+                "the display driver failed the specified graphics mode",
+            ),
+            (
+                DISP_CHANGE_NOTUPDATED,
+                DisplayChangeError::NotUpdated,
+                "DISP_CHANGE_NOTUPDATED",
+                "unable to write settings to the registry",
+            ),
+            (DISP_CHANGE_BADPARAM, DisplayChangeError::BadParam, "DISP_CHANGE_BADPARAM", "an invalid parameter was passed in"),
+            (
+                DISP_CHANGE_BADDUALVIEW,
+                DisplayChangeError::BadDualView,
+                "DISP_CHANGE_BADDUALVIEW",
+                "the settings change was unsuccessful because the system is DualView capable",
+            ),
+        ];
+        for (code, variant, name, message) in table {
+            let err = DisplayChangeError::from_code(code).unwrap();
+            assert_eq!(err, variant);
+            assert_eq!(err.code(), code);
+            assert_eq!(err.name(), name);
+            assert_eq!(err.to_string(), format!("{} ({}, code {})", message, name, code));
+        }
+        assert_eq!(DisplayChangeError::from_code(DISP_CHANGE_SUCCESSFUL), None);
+        let unknown = DisplayChangeError::from_code(-42).unwrap();
+        assert_eq!(unknown, DisplayChangeError::Other(-42));
+        assert_eq!(unknown.code(), -42);
+        assert_eq!(unknown.name(), "unknown");
+    }
+
+    #[test]
+    fn display_change_error_round_trips_through_io_error() {
+        let err = io::Error::other(DeviceDisplayChangeError {
+            device_name: r"\\.\DISPLAY2".to_string(),
+            error: DisplayChangeError::BadMode,
+        });
+        assert_eq!(DisplayChangeError::from_io_error(&err), Some(DisplayChangeError::BadMode));
+        assert!(err.to_string().starts_with(r"\\.\DISPLAY2: the graphics mode is not supported"));
 
+        let err: io::Error = DisplayChangeError::NotUpdated.into();
+        assert_eq!(DisplayChangeError::from_io_error(&err), Some(DisplayChangeError::NotUpdated));
+
+        assert_eq!(DisplayChangeError::from_io_error(&io::Error::other("something else")), None);
+        assert_eq!(DisplayChangeError::from_io_error(&io::Error::from(io::ErrorKind::NotFound)), None);
+    }
+
+    #[test]
+    fn display_change_status_keeps_its_code() {
+        for code in [DISP_CHANGE_SUCCESSFUL, DISP_CHANGE_RESTART, DISP_CHANGE_BADMODE, DISP_CHANGE_FAILED, -42] {
+            assert_eq!(DisplayChangeStatus::from_code(code).code(), code);
+        }
+        assert_eq!(DisplayChangeStatus::from_code(DISP_CHANGE_FAILED), DisplayChangeStatus::Failed(DISP_CHANGE_FAILED));
+    }
+
+    #[test]
+    fn display_number_from_name_reads_the_trailing_digits() {
+        assert_eq!(display_number_from_name(r"\\.\DISPLAY1"), Some(1));
+        assert_eq!(display_number_from_name(r"\\.\DISPLAY12"), Some(12));
+        assert_eq!(display_number_from_name(r"\\.\DISPLAYX"), None);
+        assert_eq!(display_number_from_name(""), None);
+    }
+}
@@ -1,23 +1,323 @@
+import queue
+from typing import Callable, Literal, TypedDict, overload
+
+
+MONITORINFOF_PRIMARY: int
+
+DisplayChangeStatus = Literal['successful', 'restart', 'bad_mode', 'failed']
+
+
+class MonitorNotFoundError(ValueError): ...
+class InvalidMonitorHandleError(MonitorNotFoundError): ...
+class BrightnessNotSupportedError(OSError): ...
+class DisplaySettingsError(OSError): ...
+
+
 class Monitor:
     @property
     def name(self) -> str: ...
     @property
+    def friendly_name(self) -> str | None: ...
+    @property
+    def brightness(self) -> int | None: ...
+    @brightness.setter
+    def brightness(self, value: int) -> None: ...
+    @property
+    def brightness_range(self) -> tuple[int, int, int] | None: ...
+    @property
     def size(self) -> tuple[int, int]: ...
     @property
+    def logical_size(self) -> tuple[int, int]: ...
+    @property
     def position(self) -> tuple[int, int]: ...
     @property
+    def work_area(self) -> tuple[tuple[int, int], tuple[int, int]]: ...
+    @property
+    def center(self) -> tuple[int, int]: ...
+    @property
     def scale_factor(self) -> float: ...
     @property
     def refresh_rate_millihertz(self) -> int | None: ...
     @property
+    def refresh_rate_hz(self) -> float | None: ...
+    @property
+    def refresh_rate_rational(self) -> tuple[int, int] | None: ...
+    @property
+    def bits_per_pixel(self) -> int | None: ...
+    @property
+    def flags(self) -> dict[str, bool]: ...
+    @property
+    def raw_flags(self) -> int: ...
+    @property
+    def orientation(self) -> Literal['landscape', 'portrait', 'landscape_flipped', 'portrait_flipped']: ...
+    @property
+    def orientation_degrees(self) -> Literal[0, 90, 180, 270]: ...
+    @property
+    def physical_size_mm(self) -> tuple[int, int] | None: ...
+    @property
+    def is_primary(self) -> bool: ...
+    @property
+    def is_internal(self) -> bool: ...
+    @property
+    def connection_type(self) -> str: ...
+    @property
+    def is_low_bit_depth(self) -> bool: ...
+    @property
+    def hdr_supported(self) -> bool: ...
+    @property
+    def hdr_enabled(self) -> bool: ...
+    @property
+    def serial_number(self) -> str | None: ...
+    @property
+    def manufacturer_id(self) -> str | None: ...
+    @property
+    def product_code(self) -> int | None: ...
+    @property
+    def edid(self) -> bytes | None: ...
+    @property
+    def adapter_name(self) -> str | None: ...
+    @property
+    def display_number(self) -> int | None: ...
+    @property
+    def stable_id(self) -> str | None: ...
+    @property
+    def scale_is_recommended(self) -> bool | None: ...
+    @property
+    def is_valid(self) -> bool: ...
+    @property
     def handle(self) -> int: ...
 
+    def center_rect(self, width: int, height: int) -> tuple[int, int]: ...
+    def get_dpi(self, dpi_type: Literal['effective', 'angular', 'raw'] = 'effective') -> int | None: ...
+    def get_display_modes(self) -> list[DisplayMode]: ...
+    def supported_modes(self, min_width: int = 0, min_height: int = 0, min_refresh: int = 0) -> list[DisplayMode]: ...
+    def refresh_summary(self) -> RefreshSummaryDict | None: ...
+    def supports_mode(self, width: int, height: int, refresh: int | None = None) -> bool: ...
+    def reset_scale_to_recommended(self) -> None: ...
+    def scale_info(self) -> ScaleInfoDict | None: ...
+    def get_gamma_ramp(self) -> list[list[int]]: ...
+    def set_gamma_ramp(self, ramp: list[list[int]]) -> None: ...
+    def to_dict(self) -> MonitorDict: ...
+    def refresh(self) -> None: ...
     def set_primary(self) -> None: ...
+    def __reduce__(self) -> tuple[Callable[[int], Monitor], tuple[int]]: ...
+
+
+class MonitorDict(TypedDict):
+    name: str
+    handle: int
+    position: list[int]
+    size: list[int]
+    work_area: list[list[int]]
+    scale_factor: float
+    refresh_rate_millihertz: int | None
+    is_primary: bool
+
+
+class ScaleInfoDict(TypedDict):
+    current: int
+    recommended: int
+    available: list[int]
+
+
+class RefreshSummaryDict(TypedDict):
+    current: int
+    max_at_current_res: int
+    max_overall: int
+
+
+class DisplayMode:
+    @property
+    def width(self) -> int: ...
+    @property
+    def height(self) -> int: ...
+    @property
+    def refresh_rate(self) -> int: ...
+    @property
+    def bits_per_pixel(self) -> int: ...
+
+
+class SkippedMonitorDict(TypedDict):
+    name: str | None
+    handle: int
+    error: str
+
+
+class PrimaryChangeReportDict(TypedDict):
+    repositioned: list[str]
+    skipped: list[SkippedMonitorDict]
+
+
+class MonitorConfigDict(TypedDict):
+    device_name: str
+    position: tuple[int, int]
+    size: tuple[int, int]
+    refresh_rate: int
+    bits_per_pixel: int
+    orientation: int
+    primary: bool
+
+
+class DisplayConfig:
+    def __init__(self, monitors: list[MonitorConfigDict]) -> None: ...
+    @staticmethod
+    def capture() -> DisplayConfig: ...
+    @property
+    def monitors(self) -> list[MonitorConfigDict]: ...
+
+    def apply(self) -> None: ...
+    def __reduce__(self) -> tuple[type[DisplayConfig], tuple[list[MonitorConfigDict]]]: ...
+
+
+class DisplayChangeMonitor:
+    def __init__(self) -> None: ...
+    def poll(self) -> bool: ...
+
+
+class DisplayTransaction:
+    def __init__(self) -> None: ...
+    @property
+    def result(self) -> DisplayChangeStatus | None: ...
+
+    def set_mode(self, display_name: str, width: int, height: int) -> None: ...
+    def set_refresh_rate(self, display_name: str, hz: int) -> None: ...
+    def set_position(self, display_name: str, x: int, y: int) -> None: ...
+    def set_primary(self, display_name: str) -> None: ...
+    def commit(self) -> DisplayChangeStatus: ...
+    def discard(self) -> None: ...
+    def __enter__(self) -> DisplayTransaction: ...
+    def __exit__(self, exc_type, exc_value, traceback) -> None: ...
+
+
+class DisplayChangeEvents:
+    @property
+    def queue(self) -> queue.Queue[list[Monitor] | None]: ...
+    @property
+    def closed(self) -> bool: ...
+
+    def get(self, block: bool = True, timeout: float | None = None) -> list[Monitor]: ...
+    def get_nowait(self) -> list[Monitor]: ...
+    def empty(self) -> bool: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> DisplayChangeEvents: ...
+    def __exit__(self, exc_type, exc_value, traceback) -> None: ...
+
+
+class PendingRevert:
+    @property
+    def pending(self) -> bool: ...
+
+    def confirm(self) -> bool: ...
+    def revert(self) -> None: ...
+
+
+class MonitorDpiDict(TypedDict):
+    name: str | None
+    effective: int | None
+    angular: int | None
+    raw: int | None
+
+
+class DpiDiagnosticsDict(TypedDict):
+    awareness: Literal['unaware', 'system_aware', 'per_monitor_aware', 'per_monitor_aware_v2']
+    system_dpi: int
+    monitors: list[MonitorDpiDict]
+
+
+DpiAwarenessLevel = Literal['unaware', 'system_aware', 'per_monitor_aware', 'per_monitor_aware_v2']
+
+
+class DpiAwarenessContext:
+    @property
+    def level(self) -> DpiAwarenessLevel: ...
+
+    def __enter__(self) -> DpiAwarenessContext: ...
+    def __exit__(self, exc_type, exc_value, traceback) -> None: ...
+
+
+class PixelFormatDict(TypedDict):
+    bits_per_pixel: int
+    channel_order: Literal['BGRA', 'BGR', 'indexed']
+
+
+class Watcher:
+    @property
+    def running(self) -> bool: ...
+
+    def stop(self) -> None: ...
+    def __enter__(self) -> Watcher: ...
+    def __exit__(self, exc_type, exc_value, traceback) -> None: ...
 
 
 def get_primary_monitor() -> Monitor: ...
+def snapshot_all_monitors() -> list[MonitorDict]: ...
 def get_window_monitor(hwnd: int) -> Monitor: ...
-def enumerate_monitors() -> list[Monitor]: ...
-def get_monitor_from_point(x: int, y: int) -> Monitor: ...
+def enumerate_monitors(sort: Literal['position'] | None = None, include_pseudo: bool = False) -> list[Monitor]: ...
+@overload
+def get_monitor_from_point(x: int, y: int, default: Literal['primary', 'nearest'] = 'primary') -> Monitor: ...
+@overload
+def get_monitor_from_point(x: int, y: int, default: Literal['none']) -> Monitor | None: ...
+def monitor_from_rect(x: int, y: int, width: int, height: int) -> Monitor: ...
+def get_monitor_from_cursor() -> Monitor: ...
+def get_monitor_by_name(name: str) -> Monitor: ...
+def get_monitor_by_number(number: int) -> Monitor: ...
+def get_monitor_from_handle(handle: int) -> Monitor: ...
+
+@overload
+def set_primary_monitor(display_name: str, test_only: Literal[False] = False, persist: bool = True) -> bool: ...
+@overload
+def set_primary_monitor(display_name: str, test_only: Literal[True], persist: bool = True) -> DisplayChangeStatus: ...
+def set_primary_monitor_report(display_name: str, persist: bool = True, strict: bool = False) -> PrimaryChangeReportDict: ...
+@overload
+def set_display_mode(display_name: str, width: int, height: int, test_only: Literal[False] = False, persist: bool = True) -> bool: ...
+@overload
+def set_display_mode(display_name: str, width: int, height: int, test_only: Literal[True], persist: bool = True) -> DisplayChangeStatus: ...
+def set_refresh_rate(display_name: str, hz: int, persist: bool = True) -> None: ...
+def set_orientation(display_name: str, degrees: Literal[0, 90, 180, 270], persist: bool = True) -> None: ...
+def set_monitor_position(display_name: str, x: int, y: int, persist: bool = True) -> None: ...
+def disable_monitor_safely(monitor: Monitor) -> None: ...
+def set_monitor_enabled(display_name: str, enabled: bool) -> None: ...
+def set_monitor_power(state: Literal['on', 'off', 'standby']) -> None: ...
+def set_color_temperature(display_name: str, kelvin: int) -> None: ...
+def set_hdr(display_name: str, enabled: bool) -> None: ...
+
+def get_layout_diagram() -> str: ...
+def desktop_is_rectangular() -> bool: ...
+def get_display_topology() -> Literal['extend', 'clone', 'internal', 'external', 'mixed']: ...
+def set_display_topology(mode: Literal['extend', 'clone', 'internal', 'external']) -> bool: ...
+def monitor_rows() -> list[list[Monitor]]: ...
+def monitor_at_virtual_pixel(vx: int, vy: int) -> Monitor | None: ...
+def center_rect(width: int, height: int, monitor: Monitor | None = None) -> tuple[int, int]: ...
+def get_virtual_screen_bounds() -> tuple[tuple[int, int], tuple[int, int]]: ...
+def monitor_adjacency() -> dict[str, dict[Literal['left', 'right', 'up', 'down'], str | None]]: ...
+def monitor_in_direction(monitor: Monitor, direction: Literal['left', 'right', 'up', 'down']) -> Monitor | None: ...
+def monitor_for_points(points: list[tuple[int, int]]) -> Monitor: ...
+def maximized_bounds_for_window(hwnd: int) -> tuple[Monitor, tuple[int, int, int, int]]: ...
+def suggest_primary_monitor() -> Monitor: ...
+def largest_monitor() -> Monitor: ...
+def highest_resolution_monitor() -> Monitor: ...
+def refresh_rate_groups() -> dict[int, list[Monitor]]: ...
+def refresh_rates_uniform() -> bool: ...
+def is_remote_session() -> bool: ...
+def set_all_scale_percent(percent: int) -> dict[str, str | None]: ...
+def set_scale_factor(display_name: str, percent: int) -> None: ...
+def set_all_resolution(width: int, height: int) -> dict[str, str | None]: ...
+def arrange_horizontally(order: list[Monitor]) -> None: ...
+def dpi_diagnostics() -> DpiDiagnosticsDict: ...
+def set_process_dpi_awareness(level: DpiAwarenessLevel) -> bool: ...
+def dpi_awareness(level: DpiAwarenessLevel = 'per_monitor_aware_v2') -> DpiAwarenessContext: ...
+def desktop_pixel_format() -> PixelFormatDict | None: ...
 
-def set_primary_monitor(display_name: str) -> None: ...
\ No newline at end of file
+def display_generation() -> int: ...
+def watch_dpi_changes(callback: Callable[[Monitor, int], None]) -> Watcher: ...
+def watch_display_changes(
+    callback: Callable[[list[Monitor]], None],
+    on_dpi_change: Callable[[Monitor, float], None] | None = None,
+) -> Watcher: ...
+def wait_for_display_change(timeout_ms: int | None = None) -> bool: ...
+def display_change_events() -> DisplayChangeEvents: ...
+def save_display_config() -> DisplayConfig: ...
+def restore_display_config(config: DisplayConfig) -> None: ...
+def apply_with_confirmation(config: DisplayConfig, timeout_secs: float, confirm: Callable[[], bool]) -> bool: ...
+def set_display_mode_with_confirm(display_name: str, width: int, height: int, revert_after_ms: int = 15000) -> PendingRevert: ...
+def set_taskbar_monitor(monitor: Monitor, edge: Literal['left', 'top', 'right', 'bottom']) -> None: ...
\ No newline at end of file